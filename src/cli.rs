@@ -0,0 +1,450 @@
+use crate::{
+    kind_label,
+    locale::{self, Catalog},
+    project::{ProjectConfig, UnknownKinds},
+    CommitSource, Config, DatabaseCommand, ExecutionMode, UNRELEASED,
+};
+use clap::{error::ErrorKind, parser::ValueSource, Arg, ArgAction, ArgMatches, Command};
+
+/// The help text `help.{key}` of the catalog.
+fn tr(catalog: &Catalog, key: &str) -> String {
+    catalog.get(&format!("help.{key}")).to_string()
+}
+
+/// Localized help flag, along with the headings of the help.
+fn localize(command: Command, catalog: &Catalog) -> Command {
+    let template = format!(
+        "{{about-with-newline}}\n{} {{usage}}\n\n{{all-args}}{{after-help}}",
+        tr(catalog, "usage")
+    );
+    command
+        .help_template(template)
+        .next_help_heading(tr(catalog, "options"))
+        .subcommand_help_heading(tr(catalog, "commands"))
+        .disable_help_flag(true)
+        .disable_help_subcommand(true)
+        .arg(
+            Arg::new("help")
+                .short('h')
+                .long("help")
+                .action(ArgAction::Help)
+                .help(tr(catalog, "help")),
+        )
+}
+
+fn positional(id: &'static str, name: String, help: String, catalog: &Catalog) -> Arg {
+    Arg::new(id)
+        .value_name(name)
+        .help(help)
+        .help_heading(tr(catalog, "arguments"))
+}
+
+fn option(id: &'static str, name: String, help: String) -> Arg {
+    Arg::new(id).long(id).value_name(name).help(help)
+}
+
+/// Options of every command, about the project configuration.
+fn global_args(catalog: &Catalog) -> Vec<Arg> {
+    let args = vec![
+        option("config", tr(catalog, "file"), tr(catalog, "config")),
+        option("database", tr(catalog, "file"), tr(catalog, "database")),
+        option(
+            "tag-pattern",
+            tr(catalog, "pattern"),
+            tr(catalog, "tag_pattern"),
+        ),
+        option(
+            "unknown-kinds",
+            tr(catalog, "policy"),
+            tr(catalog, "unknown_kinds"),
+        )
+        .value_parser(["other", "raw", "fail"])
+        .hide_possible_values(true),
+        option("lang", tr(catalog, "language"), tr(catalog, "lang")),
+        option(
+            "translations",
+            tr(catalog, "file"),
+            tr(catalog, "translations"),
+        ),
+    ];
+    let heading = tr(catalog, "project_options");
+    args.into_iter()
+        .map(|arg| arg.global(true).help_heading(heading.clone()))
+        .collect()
+}
+
+/// Options choosing the commits to read from git.
+fn revision_args(catalog: &Catalog) -> Vec<Arg> {
+    vec![
+        option("from", String::from("REV"), tr(catalog, "from")),
+        option("to", String::from("REV"), tr(catalog, "to")),
+        Arg::new("unreleased")
+            .long("unreleased")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["from", "to"])
+            .help(tr(catalog, "unreleased")),
+        Arg::new("input")
+            .short('i')
+            .long("input")
+            .value_name(tr(catalog, "file"))
+            .conflicts_with_all(["from", "to", "unreleased"])
+            .help(tr(catalog, "input")),
+    ]
+}
+
+fn output_arg(help: String, catalog: &Catalog) -> Arg {
+    Arg::new("output")
+        .short('o')
+        .long("output")
+        .value_name(tr(catalog, "file"))
+        .help(help)
+}
+
+/// The renderer of the notes, checked against the registered formats when running.
+fn format_args(catalog: &Catalog) -> Vec<Arg> {
+    vec![
+        option("format", tr(catalog, "label"), tr(catalog, "format")),
+        option("stylesheet", tr(catalog, "file"), tr(catalog, "stylesheet")),
+        option("template", tr(catalog, "file"), tr(catalog, "template")),
+        Arg::new("players")
+            .long("players")
+            .action(ArgAction::SetTrue)
+            .help(tr(catalog, "players")),
+    ]
+}
+
+/// Arguments of `generate`, also accepted without a command.
+fn generate_args(catalog: &Catalog) -> Vec<Arg> {
+    let mut args = vec![
+        positional(
+            "files",
+            tr(catalog, "files"),
+            tr(catalog, "generate_files"),
+            catalog,
+        )
+        .num_args(0..=2),
+        output_arg(tr(catalog, "output_notes"), catalog),
+    ];
+    args.extend(format_args(catalog));
+    args.extend(revision_args(catalog));
+    args.push(
+        option(
+            "unreleased-label",
+            tr(catalog, "label"),
+            tr(catalog, "unreleased_label"),
+        )
+        .conflicts_with_all(["from", "to", "input"]),
+    );
+    args.push(
+        Arg::new("update")
+            .long("update")
+            .action(ArgAction::SetTrue)
+            .help(tr(catalog, "update")),
+    );
+    args
+}
+
+/// The list of the kinds and the ideal commit structure, shown after the options.
+fn after_help(catalog: &Catalog) -> String {
+    let mut help = format!(
+        "\n{}\n    {}\n\n{}\n",
+        tr(catalog, "structure"),
+        tr(catalog, "structure_example"),
+        tr(catalog, "kinds")
+    );
+    for kind in ProjectConfig::default().kinds {
+        help.push_str(&format!(
+            "    {:<10}{}\n",
+            kind.name,
+            kind_label(&kind, catalog)
+        ));
+    }
+    help.push_str(&format!("\n{}\n", tr(catalog, "languages")));
+    help
+}
+
+pub fn command(catalog: &Catalog) -> Command {
+    let generate = localize(Command::new("generate"), catalog)
+        .about(tr(catalog, "generate"))
+        .args(generate_args(catalog));
+
+    let changelog = localize(Command::new("changelog"), catalog)
+        .about(tr(catalog, "changelog"))
+        .arg(
+            positional(
+                "files",
+                tr(catalog, "output"),
+                tr(catalog, "changelog_files"),
+                catalog,
+            )
+            .num_args(0..=1),
+        )
+        .arg(output_arg(tr(catalog, "output_changelog"), catalog))
+        .args(format_args(catalog));
+
+    let lint = localize(Command::new("lint"), catalog)
+        .about(tr(catalog, "lint"))
+        .args(revision_args(catalog));
+
+    let database = localize(Command::new("db"), catalog)
+        .about(tr(catalog, "db"))
+        .subcommand_required(true)
+        .subcommand(localize(Command::new("path"), catalog).about(tr(catalog, "db_path")))
+        .subcommand(localize(Command::new("tags"), catalog).about(tr(catalog, "db_tags")))
+        .subcommand(
+            localize(Command::new("forget"), catalog)
+                .about(tr(catalog, "db_forget"))
+                .arg(
+                    positional(
+                        "tag",
+                        String::from("TAG"),
+                        tr(catalog, "forget_tag"),
+                        catalog,
+                    )
+                    .required(true),
+                ),
+        );
+
+    localize(Command::new("dedma"), catalog)
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(tr(catalog, "about"))
+        .after_help(after_help(catalog))
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("version")
+                .short('V')
+                .long("version")
+                .action(ArgAction::Version)
+                .help(tr(catalog, "version")),
+        )
+        .args(global_args(catalog))
+        .args(generate_args(catalog))
+        .subcommand(generate)
+        .subcommand(changelog)
+        .subcommand(lint)
+        .subcommand(database)
+}
+
+/// The value given to `--{name}` in `args`, before they are parsed.
+fn find_option(args: &[String], name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if *arg == flag {
+            return args.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(&format!("{flag}=")) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// The catalog of the help, chosen before the command line is parsed: `--lang`,
+/// then the `lang` of the configuration file, then the environment.
+fn help_catalog(args: &[String]) -> Catalog {
+    messages_catalog(
+        find_option(args, "lang").as_deref(),
+        find_option(args, "config").as_deref(),
+    )
+}
+
+/// The catalog of the messages for `lang`, else the `lang` of the configuration file
+/// `config`, else the environment.
+pub(crate) fn messages_catalog(lang: Option<&str>, config: Option<&str>) -> Catalog {
+    let language = lang.map(str::to_string).or_else(|| {
+        ProjectConfig::load(config)
+            .ok()
+            .and_then(|project| project.lang)
+    });
+    Catalog::new(&locale::resolve(language.as_deref())[0])
+}
+
+/// The value of `id`, if the command has such an argument and it was given.
+fn value(matches: &ArgMatches, id: &str) -> Option<String> {
+    matches.try_get_one::<String>(id).ok().flatten().cloned()
+}
+
+fn flag(matches: &ArgMatches, id: &str) -> bool {
+    matches.try_get_one::<bool>(id).ok().flatten() == Some(&true)
+}
+
+pub fn parse(args: &[String]) -> Result<Config, clap::Error> {
+    let catalog = help_catalog(args);
+    let mut command = command(&catalog);
+    let matches = command.try_get_matches_from_mut(args)?;
+    // Only the project options, which are global, may come before a command
+    if let Some((name, _)) = matches.subcommand() {
+        for arg in generate_args(&Catalog::default()) {
+            if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+                let arg = match arg.get_long() {
+                    Some(long) => format!("--{long}"),
+                    None => arg.get_id().to_string(),
+                };
+                return Err(command.error(
+                    ErrorKind::ArgumentConflict,
+                    catalog.format(
+                        "messages.misplaced_option",
+                        &[("option", &arg), ("command", &name)],
+                    ),
+                ));
+            }
+        }
+    }
+    let (mode, matches) = match matches.subcommand() {
+        Some(("generate", matches)) => (ExecutionMode::Generate, matches),
+        Some(("changelog", matches)) => (ExecutionMode::Changelog, matches),
+        Some(("lint", matches)) => (ExecutionMode::Lint, matches),
+        Some(("db", matches)) => match matches.subcommand() {
+            Some(("tags", matches)) => (ExecutionMode::Database(DatabaseCommand::Tags), matches),
+            Some(("forget", matches)) => (
+                ExecutionMode::Database(DatabaseCommand::Forget(
+                    value(matches, "tag").unwrap_or_default(),
+                )),
+                matches,
+            ),
+            _ => (ExecutionMode::Database(DatabaseCommand::Path), matches),
+        },
+        _ => (ExecutionMode::Generate, &matches),
+    };
+
+    let files: Vec<String> = matches
+        .try_get_many::<String>("files")
+        .ok()
+        .flatten()
+        .map(|files| files.cloned().collect())
+        .unwrap_or_default();
+    let (mut input, mut output) = match files.as_slice() {
+        [input, output] => (Some(input.clone()), Some(output.clone())),
+        [output] => (None, Some(output.clone())),
+        _ => (None, None),
+    };
+    if let Some(file) = value(matches, "input") {
+        input = Some(file);
+    }
+    if let Some(file) = value(matches, "output") {
+        output = Some(file);
+    }
+
+    let unreleased_label = value(matches, "unreleased-label");
+    let unreleased = if flag(matches, "unreleased") || unreleased_label.is_some() {
+        Some(unreleased_label.unwrap_or_else(|| String::from(UNRELEASED)))
+    } else {
+        None
+    };
+
+    Ok(Config {
+        output,
+        source: match input {
+            Some(file) => CommitSource::File(file),
+            None => CommitSource::Git,
+        },
+        tag: String::from("tag"),
+        from: value(matches, "from"),
+        to: value(matches, "to"),
+        unreleased,
+        update: flag(matches, "update"),
+        config_file: value(matches, "config"),
+        database: value(matches, "database"),
+        tag_pattern: value(matches, "tag-pattern"),
+        unknown_kinds: value(matches, "unknown-kinds").and_then(|p| UnknownKinds::parse(&p)),
+        lang: value(matches, "lang"),
+        translations: value(matches, "translations"),
+        format: value(matches, "format"),
+        stylesheet: value(matches, "stylesheet"),
+        template: value(matches, "template"),
+        players: flag(matches, "players"),
+        mode,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn command_is_valid() {
+        command(&Catalog::default()).debug_assert();
+    }
+
+    #[test]
+    fn help_is_translated() {
+        let help = command(&Catalog::new("es")).render_help().to_string();
+        assert!(help.contains("Uso: dedma"));
+        assert!(help.contains("Nuevas funcionalidades"));
+        assert_eq!(
+            find_option(&args(&["dedma", "--lang=de", "notes.md"]), "lang").as_deref(),
+            Some("de")
+        );
+    }
+
+    #[test]
+    fn help_keys_are_resolved() {
+        for language in ["en", "fr", "es", "de"] {
+            let help = command(&Catalog::new(language)).render_help().to_string();
+            assert!(
+                !help.contains("help."),
+                "unresolved key in the {language} help"
+            );
+        }
+    }
+
+    #[test]
+    fn generate_is_the_default() {
+        let config = parse(&args(&["dedma", "logs.txt", "notes.md"])).unwrap();
+        assert!(config.mode == ExecutionMode::Generate);
+        assert!(matches!(config.source, CommitSource::File(ref file) if file == "logs.txt"));
+        assert_eq!(config.output.as_deref(), Some("notes.md"));
+
+        let config = parse(&args(&[
+            "dedma",
+            "generate",
+            "-o",
+            "notes.md",
+            "--unreleased",
+            "--lang",
+            "fr",
+        ]))
+        .unwrap();
+        assert_eq!(config.output.as_deref(), Some("notes.md"));
+        assert_eq!(config.unreleased.as_deref(), Some("Unreleased"));
+        assert_eq!(config.lang.as_deref(), Some("fr"));
+    }
+
+    #[test]
+    fn subcommands() {
+        let config = parse(&args(&["dedma", "changelog", "--tag-pattern", "v*"])).unwrap();
+        assert!(config.mode == ExecutionMode::Changelog);
+        assert_eq!(config.tag_pattern.as_deref(), Some("v*"));
+        assert!(!config.players);
+        let config = parse(&args(&["dedma", "changelog", "--players"])).unwrap();
+        assert!(config.players);
+
+        let config = parse(&args(&[
+            "dedma",
+            "--database",
+            "x.db",
+            "db",
+            "forget",
+            "v0.1.0",
+        ]))
+        .unwrap();
+        assert_eq!(config.database.as_deref(), Some("x.db"));
+        assert!(
+            config.mode == ExecutionMode::Database(DatabaseCommand::Forget(String::from("v0.1.0")))
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_errors() {
+        assert!(parse(&args(&["dedma", "--unreleased", "--from", "v0.1.0"])).is_err());
+        assert!(parse(&args(&["dedma", "--unknown-kinds", "drop"])).is_err());
+        assert!(parse(&args(&["dedma", "--output"])).is_err());
+        assert!(parse(&args(&["dedma", "changelog", "--update"])).is_err());
+        assert!(parse(&args(&["dedma", "--from", "v0.1.0", "lint"])).is_err());
+    }
+}
@@ -1,11 +1,39 @@
-use crate::ParsedLine;
+use crate::parser::ParsedLine;
 use indicatif::ProgressBar;
-use sqlx::{self, sqlite::SqlitePool};
-use std::fs::{DirBuilder, File};
+#[cfg(test)]
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{
+    self,
+    sqlite::{SqliteConnectOptions, SqlitePool},
+};
+use std::{fs::DirBuilder, path::Path};
 
 #[derive(Debug, sqlx::FromRow)]
 pub struct Commit {
+    pub kind: String,
     pub content: String,
+    pub hash: String,
+    pub author: String,
+    pub breaking: bool,
+    pub breaking_note: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct KindCommit {
+    pub content: String,
+    pub hash: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Footer {
+    pub token: String,
+    pub value: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Reference {
+    pub key: String,
+    pub closes: bool,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -13,6 +41,12 @@ pub struct Kind {
     pub kind: String,
 }
 
+#[derive(Debug, sqlx::FromRow)]
+pub struct TagCount {
+    pub tag: String,
+    pub commits: i64,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct Title {
     pub kind: String,
@@ -29,78 +63,160 @@ async fn create_database(pool: &SqlitePool) -> anyhow::Result<()> {
             title	TEXT NOT NULL,
             tag	TEXT NOT NULL,
             hash	INTEGER NOT NULL UNIQUE,
+            breaking	INTEGER NOT NULL DEFAULT 0,
+            breaking_note	TEXT NOT NULL DEFAULT '',
+            body	TEXT NOT NULL DEFAULT '',
+            author	TEXT NOT NULL DEFAULT '',
+            unreleased	INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY(id AUTOINCREMENT)
+        )",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "\
+        CREATE TABLE IF NOT EXISTS `Footer` (
+            id	INTEGER,
+            hash	TEXT NOT NULL,
+            token	TEXT NOT NULL,
+            value	TEXT NOT NULL,
+            PRIMARY KEY(id AUTOINCREMENT)
+        )",
+    )
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "\
+        CREATE TABLE IF NOT EXISTS `Reference` (
+            id	INTEGER,
+            hash	TEXT NOT NULL,
+            key	TEXT NOT NULL,
+            closes	INTEGER NOT NULL DEFAULT 0,
             PRIMARY KEY(id AUTOINCREMENT)
         )",
     )
     .execute(pool)
     .await?;
+    upgrade_database(pool).await?;
     Ok(())
 }
 
-pub async fn connect() -> anyhow::Result<SqlitePool> {
-    let pool = SqlitePool::connect("sqlite:./.dedma/dedma_db.db").await;
-    match pool {
-        Ok(pool) => {
-            return Ok(pool);
-        }
-        Err(_) => {
-            DirBuilder::new().create("./.dedma")?;
-            File::create("./.dedma/dedma_db.db")?;
-            let pool = SqlitePool::connect("sqlite:./.dedma/dedma_db.db").await;
-            match pool {
-                Ok(pool) => {
-                    create_database(&pool).await?;
-                    return Ok(pool);
-                }
-                Err(error) => {
-                    panic!("Error creating database: {error}")
-                }
-            }
+/// Adds the columns introduced since the database was first created.
+async fn upgrade_database(pool: &SqlitePool) -> anyhow::Result<()> {
+    let added = [
+        ("breaking", "INTEGER NOT NULL DEFAULT 0"),
+        ("breaking_note", "TEXT NOT NULL DEFAULT ''"),
+        ("body", "TEXT NOT NULL DEFAULT ''"),
+        ("author", "TEXT NOT NULL DEFAULT ''"),
+        ("unreleased", "INTEGER NOT NULL DEFAULT 0"),
+    ];
+    let columns: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info('Commit')")
+        .fetch_all(pool)
+        .await?;
+    for (name, definition) in added {
+        if !columns.iter().any(|column| column.0 == name) {
+            sqlx::query(&format!(
+                "ALTER TABLE `Commit` ADD COLUMN {name} {definition}"
+            ))
+            .execute(pool)
+            .await?;
         }
     }
+    Ok(())
 }
 
-pub async fn connect_test() -> anyhow::Result<SqlitePool> {
-    let pool = SqlitePool::connect("sqlite:./tests/test_db.db").await;
+/// Opens the database at `path`, creating it and its directory when needed.
+pub async fn connect(path: &str) -> anyhow::Result<SqlitePool> {
+    if let Some(directory) = Path::new(path).parent() {
+        DirBuilder::new().recursive(true).create(directory)?;
+    }
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(options).await;
     match pool {
         Ok(pool) => {
-            return Ok(pool);
+            create_database(&pool).await?;
+            Ok(pool)
         }
-        Err(_) => {
-            let _ = DirBuilder::new().create("./tests");
-            let _ = File::create("./tests/test_db.db");
-            let pool = SqlitePool::connect("sqlite:./tests/test_db.db").await;
-            match pool {
-                Ok(pool) => {
-                    create_database(&pool).await?;
-                    return Ok(pool);
-                }
-                Err(error) => {
-                    panic!("Error creating database: {error}")
-                }
-            }
+        Err(error) => {
+            anyhow::bail!("Error opening database '{path}': {error}")
         }
     }
 }
 
+#[cfg(test)]
+pub async fn connect_test() -> anyhow::Result<SqlitePool> {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await?;
+    create_database(&pool).await?;
+    Ok(pool)
+}
+
+/// Records a commit under `tag`, the pseudo-tag of `--unreleased` when `unreleased`
+/// is set. A commit already recorded under such a pseudo-tag is moved to `tag`, for
+/// example once its release is tagged, the others are left untouched.
 pub async fn add_commit(
     tag: &str,
+    unreleased: bool,
     pool: &SqlitePool,
     parsed_line: ParsedLine,
 ) -> anyhow::Result<u64> {
     let mut conn = pool.acquire().await?;
     let id = sqlx::query(
-        "INSERT INTO `Commit` (content,kind,title,tag,hash)
-    VALUES($1,$2,$3,$4,$5)",
+        "INSERT INTO `Commit`
+        (content,kind,title,tag,hash,breaking,breaking_note,body,author,unreleased)
+    VALUES($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+    ON CONFLICT(hash) DO UPDATE SET content = excluded.content, kind = excluded.kind,
+        title = excluded.title, tag = excluded.tag, breaking = excluded.breaking,
+        breaking_note = excluded.breaking_note, body = excluded.body, author = excluded.author,
+        unreleased = excluded.unreleased
+    WHERE `Commit`.unreleased = 1",
     )
     .bind(parsed_line.content)
     .bind(parsed_line.kind)
     .bind(parsed_line.title)
     .bind(tag)
-    .bind(parsed_line.hash)
+    .bind(&parsed_line.hash)
+    .bind(parsed_line.breaking)
+    .bind(parsed_line.breaking_note)
+    .bind(parsed_line.body)
+    .bind(parsed_line.author)
+    .bind(unreleased)
     .execute(&mut *conn)
     .await?
     .rows_affected();
+    if id == 0 {
+        return Ok(id);
+    }
+
+    sqlx::query("DELETE FROM `Footer` WHERE hash = $1")
+        .bind(&parsed_line.hash)
+        .execute(&mut *conn)
+        .await?;
+    for footer in parsed_line.footers {
+        sqlx::query("INSERT INTO `Footer` (hash,token,value) VALUES($1,$2,$3)")
+            .bind(&parsed_line.hash)
+            .bind(footer.token)
+            .bind(footer.value)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    sqlx::query("DELETE FROM `Reference` WHERE hash = $1")
+        .bind(&parsed_line.hash)
+        .execute(&mut *conn)
+        .await?;
+    for reference in parsed_line.references {
+        sqlx::query("INSERT INTO `Reference` (hash,key,closes) VALUES($1,$2,$3)")
+            .bind(&parsed_line.hash)
+            .bind(reference.key)
+            .bind(reference.closes)
+            .execute(&mut *conn)
+            .await?;
+    }
     Ok(id)
 }
 
@@ -112,6 +228,45 @@ pub async fn get_kinds(tag: &str, pool: &SqlitePool) -> anyhow::Result<Vec<Kind>
     Ok(kinds)
 }
 
+/// Token of the trailer holding the text of a commit in `language`, as in `Changelog-fr: ...`.
+fn changelog_token(language: &str) -> String {
+    format!("changelog-{}", language.to_lowercase())
+}
+
+pub async fn get_breaking(
+    tag: &str,
+    language: &str,
+    pool: &SqlitePool,
+) -> anyhow::Result<Vec<Commit>> {
+    let breaking: Vec<Commit> = sqlx::query_as(
+        "SELECT c.kind, COALESCE(f.value, c.content) AS content, c.hash, c.author,
+            c.breaking, c.breaking_note
+        FROM `Commit` c
+        LEFT JOIN Footer f ON f.hash = c.hash AND lower(f.token) = $2
+        WHERE c.tag = $1 AND c.breaking = 1 ORDER BY c.id",
+    )
+    .bind(tag)
+    .bind(changelog_token(language))
+    .fetch_all(pool)
+    .await?;
+    Ok(breaking)
+}
+
+pub async fn get_kind_commits(
+    tag: &str,
+    kind: &str,
+    pool: &SqlitePool,
+) -> anyhow::Result<Vec<KindCommit>> {
+    let commits: Vec<KindCommit> = sqlx::query_as(
+        "SELECT content, hash FROM `Commit` WHERE tag = $1 AND kind = $2 ORDER BY id",
+    )
+    .bind(tag)
+    .bind(kind)
+    .fetch_all(pool)
+    .await?;
+    Ok(commits)
+}
+
 pub async fn get_titles(tag: &str, kind: &str, pool: &SqlitePool) -> anyhow::Result<Vec<Title>> {
     let titles: Vec<Title> =
         sqlx::query_as("SELECT DISTINCT kind, title FROM `Commit` WHERE tag = $1 AND kind = $2")
@@ -122,46 +277,115 @@ pub async fn get_titles(tag: &str, kind: &str, pool: &SqlitePool) -> anyhow::Res
     Ok(titles)
 }
 
+/// The commits of a title, with their text in `language` when they have a trailer for it.
 pub async fn get_commits(
     tag: &str,
     title: &Title,
+    language: &str,
     pool: &SqlitePool,
 ) -> anyhow::Result<Vec<Commit>> {
     let titles: Vec<Commit> = sqlx::query_as(
-        "SELECT DISTINCT content FROM `Commit` WHERE tag = $1 AND kind = $2 AND title = $3",
+        "SELECT c.kind, COALESCE(f.value, c.content) AS content, c.hash, c.author,
+            c.breaking, c.breaking_note, MIN(c.id)
+        FROM `Commit` c
+        LEFT JOIN Footer f ON f.hash = c.hash AND lower(f.token) = $4
+        WHERE c.tag = $1 AND c.kind = $2 AND c.title = $3
+        GROUP BY 2 ORDER BY MIN(c.id)",
     )
     .bind(tag)
     .bind(&title.kind)
     .bind(&title.title)
+    .bind(changelog_token(language))
     .fetch_all(pool)
     .await?;
     Ok(titles)
 }
 
+/// The trailers of a commit, in the order of its message.
+pub async fn get_footers(hash: &str, pool: &SqlitePool) -> anyhow::Result<Vec<Footer>> {
+    let footers: Vec<Footer> =
+        sqlx::query_as("SELECT token, value FROM `Footer` WHERE hash = $1 ORDER BY id")
+            .bind(hash)
+            .fetch_all(pool)
+            .await?;
+    Ok(footers)
+}
+
+/// The issues and pull requests a commit refers to, in the order of its message.
+pub async fn get_references(hash: &str, pool: &SqlitePool) -> anyhow::Result<Vec<Reference>> {
+    let references: Vec<Reference> =
+        sqlx::query_as("SELECT key, closes FROM `Reference` WHERE hash = $1 ORDER BY id")
+            .bind(hash)
+            .fetch_all(pool)
+            .await?;
+    Ok(references)
+}
+
+/// Every recorded tag with its number of commits, in the order they were first recorded.
+pub async fn get_tags(pool: &SqlitePool) -> anyhow::Result<Vec<TagCount>> {
+    let tags: Vec<TagCount> = sqlx::query_as(
+        "SELECT tag, COUNT(*) AS commits FROM `Commit` GROUP BY tag ORDER BY MIN(id)",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(tags)
+}
+
+/// The pseudo-tags the unreleased commits are recorded under, such as `Unreleased` or the
+/// `--unreleased-label` they were written with.
+pub async fn get_unreleased_tags(pool: &SqlitePool) -> anyhow::Result<Vec<String>> {
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT DISTINCT tag FROM `Commit` WHERE unreleased = 1 ORDER BY tag")
+            .fetch_all(pool)
+            .await?;
+    Ok(tags)
+}
+
+/// The tags of the released commits, leaving out the pseudo-tags of the unreleased ones, in
+/// the order they were first recorded.
+pub async fn get_released_tags(pool: &SqlitePool) -> anyhow::Result<Vec<String>> {
+    let tags: Vec<String> = sqlx::query_scalar(
+        "SELECT tag FROM `Commit` GROUP BY tag HAVING MAX(unreleased) = 0 ORDER BY MIN(id)",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(tags)
+}
+
+/// Removes the commits recorded under `tag`, their footers and their references, returning
+/// how many commits were removed.
+pub async fn forget_tag(tag: &str, pool: &SqlitePool) -> anyhow::Result<u64> {
+    let mut transaction = pool.begin().await?;
+    sqlx::query("DELETE FROM Footer WHERE hash IN (SELECT hash FROM `Commit` WHERE tag = $1)")
+        .bind(tag)
+        .execute(&mut *transaction)
+        .await?;
+    sqlx::query("DELETE FROM `Reference` WHERE hash IN (SELECT hash FROM `Commit` WHERE tag = $1)")
+        .bind(tag)
+        .execute(&mut *transaction)
+        .await?;
+    let removed = sqlx::query("DELETE FROM `Commit` WHERE tag = $1")
+        .bind(tag)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+    transaction.commit().await?;
+    Ok(removed)
+}
+
 pub async fn record_commits(
     tag: &str,
+    unreleased: bool,
     pool: &SqlitePool,
     parsed_lines: Vec<ParsedLine>,
     progress: Option<&ProgressBar>,
 ) -> anyhow::Result<i32> {
     let mut line_recorded = 0;
     for line in parsed_lines {
-        let id = add_commit(tag, pool, line).await;
-        match id {
-            Ok(_) => {
-                line_recorded += 1;
-                if let Some(p) = progress {
-                    p.inc(1);
-                }
-            }
-            Err(error) => {
-                if !error
-                    .to_string()
-                    .contains("(code: 2067) UNIQUE constraint failed")
-                {
-                    panic!("{}", error);
-                }
-            }
+        add_commit(tag, unreleased, pool, line).await?;
+        line_recorded += 1;
+        if let Some(p) = progress {
+            p.inc(1);
         }
     }
 
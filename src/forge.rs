@@ -0,0 +1,302 @@
+//! Links to the commits, the compare pages and the issues of the forge hosting the
+//! repository.
+
+use crate::parser;
+use serde::Deserialize;
+use std::{collections::BTreeMap, process::Command};
+
+/// The forges whose URLs dedma knows.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket,
+}
+
+impl Forge {
+    /// The forge of `repository`, from its host. The self-hosted forges other than
+    /// GitLab are taken for GitHub, whose URLs Gitea and Forgejo share.
+    pub fn guess(repository: &str) -> Forge {
+        let host = repository
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split('/').next())
+            .unwrap_or_default()
+            .to_lowercase();
+        if host.contains("gitlab") {
+            Forge::GitLab
+        } else if host == "bitbucket.org" {
+            Forge::Bitbucket
+        } else if host == "codeberg.org" || host.contains("gitea") {
+            Forge::Gitea
+        } else {
+            Forge::GitHub
+        }
+    }
+
+    /// URL of a commit, with `{repository}` and `{hash}` placeholders.
+    fn commit_url(self) -> &'static str {
+        match self {
+            Forge::GitHub | Forge::Gitea => "{repository}/commit/{hash}",
+            Forge::GitLab => "{repository}/-/commit/{hash}",
+            Forge::Bitbucket => "{repository}/commits/{hash}",
+        }
+    }
+
+    /// URL of the changes from `{from}` to `{to}`.
+    fn compare_url(self) -> &'static str {
+        match self {
+            Forge::GitHub | Forge::Gitea => "{repository}/compare/{from}...{to}",
+            Forge::GitLab => "{repository}/-/compare/{from}...{to}",
+            Forge::Bitbucket => "{repository}/branches/compare/{to}%0D{from}",
+        }
+    }
+
+    /// URL of the issue or pull request `{number}`. GitHub and Gitea take an issue
+    /// URL to the pull request of that number.
+    fn issue_url(self) -> &'static str {
+        match self {
+            Forge::GitHub | Forge::Gitea | Forge::Bitbucket => "{repository}/issues/{number}",
+            Forge::GitLab => "{repository}/-/issues/{number}",
+        }
+    }
+}
+
+/// The web page of a git remote: `git@github.com:owner/project.git` and
+/// `https://github.com/owner/project.git` give `https://github.com/owner/project`.
+/// The remotes that are not on a web server, such as local paths, have none.
+pub fn web_url(remote: &str) -> Option<String> {
+    let remote = remote.trim().trim_end_matches('/');
+    let remote = remote.strip_suffix(".git").unwrap_or(remote);
+    let (host, path) = if let Some(rest) = remote.strip_prefix("ssh://") {
+        let rest = rest.split_once('@').map_or(rest, |(_, rest)| rest);
+        let (host, path) = rest.split_once('/')?;
+        // The port of ssh is not the one of the web server
+        (host.split(':').next()?, path)
+    } else if let Some(rest) = remote
+        .strip_prefix("https://")
+        .or_else(|| remote.strip_prefix("http://"))
+    {
+        let rest = rest.split_once('@').map_or(rest, |(_, rest)| rest);
+        rest.split_once('/')?
+    } else {
+        // scp-like syntax, `user@host:path`
+        let (user_host, path) = remote.split_once(':')?;
+        let host = user_host
+            .split_once('@')
+            .map_or(user_host, |(_, host)| host);
+        if host.is_empty() || host.contains('/') {
+            return None;
+        }
+        (host, path)
+    };
+    if path.is_empty() {
+        return None;
+    }
+    Some(format!("https://{host}/{}", path.trim_start_matches('/')))
+}
+
+/// The web page of the `origin` remote of the repository, if it has one.
+pub fn origin() -> Option<String> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    web_url(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The URLs of the commits, compare pages and issues of a repository.
+#[derive(Debug, Default)]
+pub struct Links {
+    repository: Option<String>,
+    commit: Option<String>,
+    compare: Option<String>,
+    /// Still holding `{repository}`, which changes for the issues of other projects.
+    issue: Option<String>,
+    /// URL of the keys of each project of the other trackers.
+    trackers: BTreeMap<String, String>,
+}
+
+impl Links {
+    /// The links of `repository` on `forge`, guessed when it is `None`. The URL
+    /// templates of the configuration, `commit`, `compare` and `issue`, win over the forge.
+    pub fn new(
+        repository: Option<&str>,
+        forge: Option<Forge>,
+        commit: Option<&str>,
+        compare: Option<&str>,
+        issue: Option<&str>,
+    ) -> Links {
+        let repository = repository.map(|repository| repository.trim_end_matches('/'));
+        let forge = repository.map(|repository| forge.unwrap_or_else(|| Forge::guess(repository)));
+        let url = |template: Option<&str>, default: fn(Forge) -> &'static str| {
+            let template = template.or(forge.map(default))?;
+            Some(template.replace("{repository}", repository.unwrap_or_default()))
+        };
+        Links {
+            repository: repository.map(str::to_string),
+            commit: url(commit, Forge::commit_url),
+            compare: url(compare, Forge::compare_url),
+            issue: issue.or(forge.map(Forge::issue_url)).map(str::to_string),
+            trackers: BTreeMap::new(),
+        }
+    }
+
+    /// The same links, with the URLs of the keys of other trackers, such as
+    /// `https://example.atlassian.net/browse/{key}`, by project.
+    pub fn with_trackers(self, trackers: BTreeMap<String, String>) -> Links {
+        Links { trackers, ..self }
+    }
+
+    /// The page of a commit. Only full hashes are linked, those of the commits read
+    /// from a file may be made up.
+    pub fn commit(&self, hash: &str) -> Option<String> {
+        let full = hash.len() >= 40 && hash.chars().all(|c| c.is_ascii_hexdigit());
+        let template = self.commit.as_ref().filter(|_| full)?;
+        Some(template.replace("{hash}", hash))
+    }
+
+    /// The page of the changes from `from` to `to`.
+    pub fn compare(&self, from: &str, to: &str) -> Option<String> {
+        let template = self.compare.as_ref()?;
+        Some(template.replace("{from}", from).replace("{to}", to))
+    }
+
+    /// The page of the issue or pull request `key`: `#123` in the repository,
+    /// `owner/project#123` in another project of its forge, and keys such as `ABC-123`
+    /// on the tracker of their project.
+    pub fn reference(&self, key: &str) -> Option<String> {
+        if let Some(project) = parser::tracker(key) {
+            return Some(self.trackers.get(project)?.replace("{key}", key));
+        }
+        let (project, number) = key.split_once('#')?;
+        let template = self.issue.as_ref()?;
+        let repository = match (project, &self.repository) {
+            ("", repository) => repository.clone().unwrap_or_default(),
+            (project, Some(repository)) if template.contains("{repository}") => {
+                let (scheme, rest) = repository.split_once("://")?;
+                let host = rest.split('/').next()?;
+                format!("{scheme}://{host}/{project}")
+            }
+            _ => return None,
+        };
+        Some(
+            template
+                .replace("{repository}", &repository)
+                .replace("{number}", number),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remotes_give_web_pages() {
+        let github = Some(String::from("https://github.com/owner/project"));
+        assert_eq!(web_url("git@github.com:owner/project.git"), github);
+        assert_eq!(web_url("https://github.com/owner/project.git\n"), github);
+        assert_eq!(web_url("ssh://git@github.com:22/owner/project"), github);
+        assert_eq!(web_url("https://token@github.com/owner/project/"), github);
+        assert_eq!(web_url("/srv/git/project.git"), None);
+        assert_eq!(web_url("../project"), None);
+    }
+
+    #[test]
+    fn forges_have_their_links() {
+        let hash = "7e51b36b10ef5e9311cba75417f375df891efbc0";
+        let links = Links::new(
+            Some("https://gitlab.com/owner/project/"),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            links.commit(hash).as_deref(),
+            Some("https://gitlab.com/owner/project/-/commit/7e51b36b10ef5e9311cba75417f375df891efbc0")
+        );
+        assert_eq!(
+            links.compare("v0.1.0", "v0.2.0").as_deref(),
+            Some("https://gitlab.com/owner/project/-/compare/v0.1.0...v0.2.0")
+        );
+        assert_eq!(links.commit("0123456789abcdef"), None);
+
+        let links = Links::new(
+            Some("https://bitbucket.org/owner/project"),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert_eq!(
+            links.compare("v0.1.0", "v0.2.0").as_deref(),
+            Some("https://bitbucket.org/owner/project/branches/compare/v0.2.0%0Dv0.1.0")
+        );
+        let links = Links::new(
+            Some("https://git.example.com/project"),
+            Some(Forge::GitLab),
+            Some("https://cgit.example.com/project/commit/?id={hash}"),
+            None,
+            None,
+        );
+        assert!(links
+            .commit(hash)
+            .unwrap()
+            .starts_with("https://cgit.example.com/"));
+        assert!(links
+            .compare("v0.1.0", "v0.2.0")
+            .unwrap()
+            .ends_with("/project/-/compare/v0.1.0...v0.2.0"));
+        assert_eq!(Links::new(None, None, None, None, None).commit(hash), None);
+    }
+
+    #[test]
+    fn references_have_their_links() {
+        let trackers = BTreeMap::from([(
+            String::from("ABC"),
+            String::from("https://example.atlassian.net/browse/{key}"),
+        )]);
+        let links = Links::new(
+            Some("https://gitlab.com/owner/project"),
+            None,
+            None,
+            None,
+            None,
+        )
+        .with_trackers(trackers);
+        assert_eq!(
+            links.reference("#12").as_deref(),
+            Some("https://gitlab.com/owner/project/-/issues/12")
+        );
+        assert_eq!(
+            links.reference("other/tool#9").as_deref(),
+            Some("https://gitlab.com/other/tool/-/issues/9")
+        );
+        assert_eq!(
+            links.reference("ABC-123").as_deref(),
+            Some("https://example.atlassian.net/browse/ABC-123")
+        );
+        assert_eq!(links.reference("XYZ-1"), None);
+        assert_eq!(links.reference("RFC 42"), None);
+
+        let links = Links::new(
+            None,
+            None,
+            None,
+            None,
+            Some("https://bugs.example.com/show_bug.cgi?id={number}"),
+        );
+        assert_eq!(
+            links.reference("#12").as_deref(),
+            Some("https://bugs.example.com/show_bug.cgi?id=12")
+        );
+        assert_eq!(links.reference("other/tool#9"), None);
+    }
+}
@@ -1,11 +1,20 @@
+mod cli;
 mod data_access;
+mod forge;
+mod locale;
+mod parser;
+mod project;
+pub mod render;
 
-use anyhow::Ok;
+use forge::Links;
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use locale::Catalog;
+use project::{KindConfig, ProjectConfig, UnknownKinds};
+use render::{Atom, Debian, Entry, Html, Reference, Release, Rpm, Rss, Scope, Section, Template};
+pub use render::{Formats, Renderer};
 use sqlx::SqlitePool;
 use std::{
-    collections::HashMap,
-    env, fmt,
+    fmt,
     fs::{self, File},
     io::Write,
     process::Command,
@@ -19,62 +28,91 @@ enum CommitSource {
 
 #[derive(PartialEq)]
 enum ExecutionMode {
-    Help,
-    Execute,
+    Generate,
+    Changelog,
+    Lint,
+    Database(DatabaseCommand),
 }
 
-#[derive(Debug, PartialEq)]
-struct ParsedLine {
-    kind: String,
-    title: String,
-    content: String,
-    hash: String,
+/// What `dedma db` does with the database.
+#[derive(PartialEq)]
+enum DatabaseCommand {
+    Path,
+    Tags,
+    Forget(String),
 }
 
 pub struct Config {
-    output: String,
+    output: Option<String>,
     source: CommitSource,
     tag: String,
+    from: Option<String>,
+    to: Option<String>,
+    /// Label of the pseudo-tag the commits since the latest tag are recorded under.
+    unreleased: Option<String>,
+    /// Insert the notes into the existing output file instead of overwriting it.
+    update: bool,
+    /// Project configuration file to read instead of the `.dedma.toml` of the repository.
+    config_file: Option<String>,
+    database: Option<String>,
+    tag_pattern: Option<String>,
+    unknown_kinds: Option<UnknownKinds>,
+    /// Language asked for with `--lang`, before the configuration and the environment.
+    lang: Option<String>,
+    /// Translation file to read instead of the one of the configuration.
+    translations: Option<String>,
+    /// Format to write instead of the one of the configuration.
+    format: Option<String>,
+    /// Stylesheet of the HTML notes instead of the one of the configuration.
+    stylesheet: Option<String>,
+    /// Template of the notes instead of the one of the configuration.
+    template: Option<String>,
+    /// Leave out the kinds only of interest to the developers.
+    players: bool,
     mode: ExecutionMode,
 }
 
 impl Config {
-    pub fn build(args: &[String]) -> Config {
-        let mut output = String::from("whats_new.md");
-        let mut source = CommitSource::Git;
-        let tag = String::from("tag");
-        if args.contains(&String::from("--help")) {
-            return Config {
-                output,
-                source,
-                tag,
-                mode: ExecutionMode::Help,
-            };
-        }
-        if args.len() >= 3 {
-            source = CommitSource::File(args[1].clone());
-            output = args[2].clone();
-        } else if args.len() >= 2 {
-            output = args[1].clone();
-        }
-        // println!("{output}");
-        Config {
-            output,
-            source,
-            tag,
-            mode: ExecutionMode::Execute,
-        }
+    /// Reads the command line. The error prints the help, the version or the usage
+    /// problem and exits with the matching code through `clap::Error::exit`.
+    pub fn build(args: &[String]) -> Result<Config, clap::Error> {
+        cli::parse(args)
+    }
+
+    /// "Application error" in the language of the messages, to print before the errors
+    /// of `run`.
+    pub fn error_label(&self) -> String {
+        cli::messages_catalog(self.lang.as_deref(), self.config_file.as_deref())
+            .get("messages.application_error")
+            .to_string()
     }
 }
 
-fn get_tag() -> anyhow::Result<(String, String)> {
+/// The commits to read from git, `from..to`, and the tag they are recorded under.
+/// Without `from` every commit up to `to` is read.
+struct Revisions {
+    from: Option<String>,
+    to: Option<String>,
+    tag: String,
+}
+
+/// Full commit messages, as `hash US author US message RS` records (see `parser::split_all`).
+const GIT_LOG_FORMAT: &str = "--format=%H%x1f%an%x1f%B%x1e";
+/// `--output` writing the notes to the standard output.
+const STDOUT: &str = "-";
+/// Pseudo-tag of the commits since the latest tag, when `--unreleased-label` is not given.
+const UNRELEASED: &str = "Unreleased";
+
+fn get_tag(pattern: Option<&str>) -> anyhow::Result<(String, String)> {
     let tags = Command::new("git")
         .arg("tag")
         .arg("--sort=-v:refname")
+        .arg("--list")
+        .args(pattern)
         .output()?;
 
     let tags = String::from_utf8_lossy(&tags.stdout).trim().to_string();
-    if tags.len() <= 0 {
+    if tags.is_empty() {
         return Ok(("no_tag".to_string(), "no_tag".to_string()));
     }
 
@@ -96,212 +134,639 @@ fn get_tag() -> anyhow::Result<(String, String)> {
     }
 }
 
-fn read_from_git() -> anyhow::Result<String> {
-    let tags = get_tag()?;
+/// Fails with git's own message when `rev` does not name a commit.
+fn verify_revision(rev: &str, catalog: &Catalog) -> anyhow::Result<()> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--verify")
+        .arg("--quiet")
+        .arg(format!("{rev}^{{commit}}"))
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(catalog.format("messages.unknown_revision", &[("rev", &rev)]));
+    }
+    Ok(())
+}
 
-    let commits;
-    // print!("git log ");
-    if tags.0 != tags.1 {
-        let args = format!("{}..{}", tags.0, tags.1);
-        commits = Command::new("git")
-            .arg("log")
-            .arg(&args)
-            .arg("--format=%s :%H")
-            .output()?;
+/// The newest tag matching `pattern` reachable from the parent of `rev`, if any.
+fn previous_tag(rev: &str, pattern: Option<&str>) -> anyhow::Result<Option<String>> {
+    latest_tag(&format!("{rev}^"), pattern)
+}
 
-        // print!("{} ",&args);
-    } else {
-        commits = Command::new("git")
-            .arg("log")
-            .arg("--format=%s :%H")
-            .output()?;
+/// The newest tag matching `pattern` reachable from `rev`, if any.
+fn latest_tag(rev: &str, pattern: Option<&str>) -> anyhow::Result<Option<String>> {
+    let mut command = Command::new("git");
+    command.arg("describe").arg("--tags").arg("--abbrev=0");
+    if let Some(pattern) = pattern {
+        command.arg("--match").arg(pattern);
+    }
+    let output = command.arg(rev).output()?;
+    let tag = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || tag.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(tag))
+}
+
+fn get_revisions(
+    from: Option<&str>,
+    to: Option<&str>,
+    pattern: Option<&str>,
+    catalog: &Catalog,
+) -> anyhow::Result<Revisions> {
+    if from.is_none() && to.is_none() {
+        let tags = get_tag(pattern)?;
+        if tags.0 != tags.1 {
+            return Ok(Revisions {
+                from: Some(tags.0),
+                to: Some(tags.1.clone()),
+                tag: tags.1,
+            });
+        }
+        return Ok(Revisions {
+            from: None,
+            to: None,
+            tag: tags.1,
+        });
+    }
+
+    let to = to.unwrap_or("HEAD");
+    verify_revision(to, catalog)?;
+    let from = match from {
+        Some(from) => {
+            verify_revision(from, catalog)?;
+            Some(from.to_string())
+        }
+        None => previous_tag(to, pattern)?,
+    };
+    Ok(Revisions {
+        from,
+        to: Some(to.to_string()),
+        tag: to.to_string(),
+    })
+}
+
+/// When a release was made.
+#[derive(Clone)]
+struct ReleaseDate {
+    /// As `YYYY-MM-DD`, in the time zone of the committer.
+    day: String,
+    /// Unix time.
+    time: i64,
+}
+
+impl ReleaseDate {
+    /// Reads the `YYYY-MM-DD UNIX_TIME` written by git.
+    fn parse(date: &str) -> Option<ReleaseDate> {
+        let (day, time) = date.trim().split_once(' ')?;
+        Some(ReleaseDate {
+            day: day.to_string(),
+            time: time.parse().ok()?,
+        })
+    }
+}
+
+/// The date of the commit `rev` points to.
+fn get_date(rev: &str) -> anyhow::Result<Option<ReleaseDate>> {
+    let output = Command::new("git")
+        .arg("log")
+        .arg("-1")
+        .arg("--format=%cs %ct")
+        .arg(rev)
+        .output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(ReleaseDate::parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Every tag matching `pattern` with its creation date, oldest version first.
+fn get_all_tags(pattern: Option<&str>) -> anyhow::Result<Vec<(String, ReleaseDate)>> {
+    let output = Command::new("git")
+        .arg("tag")
+        .arg("--sort=v:refname")
+        .arg("--format=%(refname:short) %(creatordate:short) %(creatordate:unix)")
+        .arg("--list")
+        .args(pattern)
+        .output()?;
+    let mut tags = vec![];
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some((tag, date)) = line.split_once(' ') {
+            if let Some(date) = ReleaseDate::parse(date) {
+                tags.push((tag.to_string(), date));
+            }
+        }
+    }
+    Ok(tags)
+}
+
+/// The commits since the latest tag, recorded under the `label` pseudo-tag.
+fn get_unreleased_revisions(label: &str, pattern: Option<&str>) -> anyhow::Result<Revisions> {
+    Ok(Revisions {
+        from: latest_tag("HEAD", pattern)?,
+        to: Some(String::from("HEAD")),
+        tag: label.to_string(),
+    })
+}
+
+fn read_from_git(revisions: &Revisions) -> anyhow::Result<String> {
+    let mut command = Command::new("git");
+    command.arg("log").arg(GIT_LOG_FORMAT);
+    match (&revisions.from, &revisions.to) {
+        (Some(from), Some(to)) => command.arg(format!("{from}..{to}")),
+        (None, Some(to)) => command.arg(to),
+        _ => &mut command,
+    };
+    let commits = command.output()?;
+    if !commits.status.success() {
+        anyhow::bail!(
+            "git log failed: {}",
+            String::from_utf8_lossy(&commits.stderr).trim()
+        );
     }
-    // println!("--format=\"%s :%H\"");
 
     Ok(String::from_utf8_lossy(&commits.stdout).to_string())
 }
 
 pub async fn run(config: Config) -> anyhow::Result<()> {
-    if config.mode == ExecutionMode::Help {
-        if env::var("LANG_FR").is_ok() {
-            println!("Dedma v0.1.2
-Générateur de notes de versions (Release notes)
-Un outil qui converti vos derniers commit en notes de version.
-
-La note est générée en anglais par défaut.
-
-Pour générer une note en français, ajouter `LANG_FR` dans les variables d'environnement.
-Pour les systèmes basés sur Unix
-    LANG_FR=1 
-
-Pour Windows PowerShell
-    $Env:LANG_FR=1
-
-Générer des notes à partir des commit dans un fichier
-    dedma fichier_d_entree fichier_de_sortie
-
-Générer les notes à partir des commit de Git
-    dedma fichier_de_sortie
-    
-ou 
-
-    dedma
-
-pour générer les notes dans le fichier `whats_new.md`
-
-Structure de commit idéale
-    type (titre): contenu
-
-Le `titre` et le `contenu` peuvent être ce que vous voulez.
-
-Voici une liste des types supportés actuellement, classés par ordre d'apparence dans la note générées.
-
- ______________________________________
-    
-|   type   | nom complet               |
-| -------- | ------------------------- |
-|   feat   | Nouvelles fonctionnalités |
-|   fix    | Correction d'erreur       |
-|  chore   | Chore                     |
-| refactor | Refactoring               |
-|   docs   | Documentation             |
-|  style   | Style de Code             |
-|   test   | Test                      |
-|   perf   | Performances              |
-|    ci    | Déploiements Continue     |
-|  build   | Système de Build          |
-|  revert  | Annulations               |
-|  update  | Mise à jour               |
- --------------------------------------");
-        }else{
-            println!("Dedma v0.1.2
-Release notes generator
-A Command Line Interface (CLI) that generates release notes from your latest commits. 
-
-The release notes are generated in english by default.
-
-To generate the release notes in french add `LANG_FR` to the environment variables
-For systems based on Unix
-    LANG_FR=1 
-
-For Windows PowerShell
-    $Env:LANG_FR=1
-
-Get the commits from a file
-    dedma input_file output file
-
-Get them directly from git
-    dedma output_file
-    
-        or 
-
-    dedma
-
-to generate the notes in the file `whats_new.md`
-
-Ideal commit structure
-    kind (title): content
-For `title` and `content` you can put whatever you want.  
-Here are the supported `kind` right now in the order of appearance in the generated notes
-
-  ______________________________________
-
-|   kind   | full name                   |
-| :------: | --------------------------- |
-|   feat   | New features                |
-|   fix    | Bug fix                     |
-|  chore   | Chore                       |
-| refactor | Refactoring                 |
-|   docs   | Documentation               |
-|  style   | Code Style                  |
-|   test   | Test                        |
-|   perf   | Performances                |
-|    ci    | Continuous Integration (CI) |
-|  build   | Build System                |
-|  revert  | Reverts                     |
-|  update  | Updates                     |
-  ______________________________________
-            ");
-        }
-        return Ok(());
+    run_with(config, Formats::default()).await
+}
+
+/// Runs dedma with `formats` to choose from, for tools adding their own renderers.
+/// The built-in formats with settings in the configuration, such as `html` or `debian`,
+/// are replaced by ones using these settings (see `configure_formats`), those given
+/// to `Formats::register` are kept.
+pub async fn run_with(config: Config, mut formats: Formats) -> anyhow::Result<()> {
+    let mut project = ProjectConfig::load(config.config_file.as_deref())?;
+    if let Some(database) = &config.database {
+        project.database = database.clone();
+    }
+    if config.tag_pattern.is_some() {
+        project.tag_pattern = config.tag_pattern.clone();
     }
+    if config.translations.is_some() {
+        project.translations = config.translations.clone();
+    }
+    if let Some(policy) = config.unknown_kinds {
+        project.unknown_kinds = policy;
+    }
+    if config.stylesheet.is_some() {
+        project.stylesheet = config.stylesheet.clone();
+    }
+    if config.template.is_some() {
+        project.template = config.template.clone();
+    }
+    if config.players {
+        project.players = true;
+    }
+    if project.repository.is_none() {
+        project.repository = forge::origin();
+    }
+    let pattern = project.tag_pattern.as_deref();
 
-    let contents;
-    let tag;
-    if let CommitSource::File(file) = config.source {
-        // println!("Gathering commits from '{file}...'");
-        contents = fs::read_to_string(file)?;
-        tag = config.tag;
-    } else {
-        // println!("Gathering commits from Git repository...");
-        contents = read_from_git()?;
-        let tagi = get_tag()?;
-        tag = tagi.1;
-    }
-    let size: u64 = contents.lines().count().try_into().unwrap();
-    if env::var("LANG_FR").is_ok() {
-        println!("Generation de {size} notes dans '{}'", config.output);
-    } else {
-        println!("Generating {size} notes in '{}'", config.output);
+    let requested = config.lang.as_deref().or(project.lang.as_deref());
+    let mut catalogs = vec![];
+    for language in locale::resolve(requested) {
+        catalogs.push(load_catalog(&language, requested.is_some(), &project)?);
     }
-    let progress = get_progress_bar(size * 3);
+    // Messages are written in the first language
+    let catalog = &catalogs[0];
+
+    // A template replaces the format of the configuration, not the one asked for
+    let template = project.template.as_ref().map(|_| "template");
+    let format = config
+        .format
+        .as_deref()
+        .or(template)
+        .or(project.format.as_deref())
+        .unwrap_or("markdown");
+    configure_formats(&mut formats, &project)?;
+    let renderer = match formats.get(format) {
+        Some(renderer) => renderer,
+        None => anyhow::bail!(catalog.format(
+            "messages.unknown_format",
+            &[
+                ("format", &format),
+                ("formats", &formats.names().join(", "))
+            ]
+        )),
+    };
+
+    match &config.mode {
+        ExecutionMode::Generate => {}
+        ExecutionMode::Changelog => {
+            let output = config
+                .output
+                .clone()
+                .unwrap_or_else(|| format!("CHANGELOG.{}", renderer.extension()));
+            return write_changelog(&output, &project, &catalogs, renderer).await;
+        }
+        ExecutionMode::Lint => return lint(&config, &project, catalog),
+        ExecutionMode::Database(command) => {
+            return manage_database(command, &project, catalog).await
+        }
+    }
+    let output = config
+        .output
+        .clone()
+        .or(project.output.clone())
+        .unwrap_or_else(|| format!("whats_new.{}", renderer.extension()));
+
+    let outputs = localized_outputs(&output, &catalogs);
+
+    let (contents, revisions, date) = read_commits(&config, pattern, catalog)?;
+    let size: u64 = parser::count(&contents).try_into().unwrap();
+    print_generating(catalog, size, &outputs);
+    let progress = get_progress_bar(size * (2 + outputs.len() as u64));
 
     // Parsing the commits
-    let parsed_lines = split_all(&contents, Some(&progress));
-    // println!("{} commits found", parsed_lines.len());
+    let parsed_lines = parse_commits(&contents, &project, catalog, &progress);
 
     // Recording them to the database
-    let pool = data_access::connect().await?;
-    let _ = data_access::record_commits(&tag, &pool, parsed_lines, Some(&progress)).await?;
-    // println!("{} new lines recorded", line_recorded);
-    // Writing the release note
-    // println!("Writing the release note in '{}'...", config.output);
-    let notes = generate_release_notes(&tag, &pool, Some(&progress)).await?;
-    // println!("{}",notes);
-    let _ = write_release_note(&config.output, notes)?;
+    let pool = data_access::connect(&project.database).await?;
+    // The labels the unreleased notes were written under, read before recording moves
+    // their commits to the new tag
+    let mut labels = data_access::get_unreleased_tags(&pool).await?;
+    labels.push(
+        config
+            .unreleased
+            .clone()
+            .unwrap_or_else(|| UNRELEASED.to_string()),
+    );
+    let unreleased = config.unreleased.is_some();
+    let _ = data_access::record_commits(
+        &revisions.tag,
+        unreleased,
+        &pool,
+        parsed_lines,
+        Some(&progress),
+    )
+    .await?;
+    for (output, catalog) in &outputs {
+        let release = build_release(
+            &revisions,
+            date.clone(),
+            &project,
+            catalog,
+            &pool,
+            Some(&progress),
+        )
+        .await?;
+        if config.update {
+            insert_release_note(output, renderer, &release, &labels, catalog)?;
+        } else {
+            let notes = match config.unreleased {
+                Some(_) => renderer.render_section(&release)?,
+                None => renderer.render(&release)?,
+            };
+            write_release_note(output, notes)?;
+        }
+    }
     progress.finish_with_message("Done");
 
     Ok(())
 }
 
-fn split_all(contents: &str, progress: Option<&ProgressBar>) -> Vec<ParsedLine> {
-    let mut res: Vec<ParsedLine> = Vec::new();
-    for line in contents.lines() {
-        res.push(split_one(line.trim()));
-        if let Some(p) = progress {
-            p.inc(1);
+/// Registers the built-in formats set up by the configuration: `html` with its
+/// `stylesheet`, `debian` and `rpm` with the `[package]` settings, `atom` and `rss`
+/// with the `[feed]` ones. The `template` becomes the `template` format.
+fn configure_formats(formats: &mut Formats, project: &ProjectConfig) -> anyhow::Result<()> {
+    if let Some(file) = &project.template {
+        formats.configure("template", Template::read(file)?);
+    }
+    match project.stylesheet.as_deref() {
+        Some("default") => {
+            formats.configure("html", Html::with_stylesheet(render::STYLESHEET));
         }
+        Some(file) => {
+            let stylesheet = fs::read_to_string(file)
+                .map_err(|error| anyhow::anyhow!("Could not read '{file}': {error}"))?;
+            formats.configure("html", Html::with_stylesheet(&stylesheet));
+        }
+        None => {}
     }
-    res
+    let package = project.package();
+    let feed = project.feed(&package);
+    formats.configure(
+        "debian",
+        Debian {
+            package: package.clone(),
+        },
+    );
+    formats.configure("rpm", Rpm { package });
+    formats.configure("atom", Atom { feed: feed.clone() });
+    formats.configure("rss", Rss { feed });
+    Ok(())
 }
 
-fn split_one(line: &str) -> ParsedLine {
-    let mut kind = String::from("other");
-    let mut title = String::from("other");
-    let content;
-    let hash;
-    let parts: Vec<&str> = line.split(':').collect();
+/// The catalog of `language` with the translation file of the project over it.
+/// Problems that don't prevent writing the notes are printed as warnings.
+fn load_catalog(
+    language: &str,
+    requested: bool,
+    project: &ProjectConfig,
+) -> anyhow::Result<Catalog> {
+    let mut catalog = Catalog::new(language);
+    if requested && !locale::is_supported(language) {
+        eprintln!(
+            "{}",
+            catalog.format("messages.unknown_language", &[("lang", &language)])
+        );
+    }
 
-    if parts.len() >= 3 {
-        if parts[0].contains('(') {
-            let sc: Vec<&str> = parts[0].split('(').collect();
-            kind = sc[0].to_lowercase().trim().to_string();
-            title = sc[1].replace(')', " ").trim().to_lowercase().to_string();
-        } else {
-            kind = parts[0].trim().to_lowercase().to_string();
+    let Some(translations) = &project.translations else {
+        return Ok(catalog);
+    };
+    // With a `{lang}` placeholder, the languages without a file keep the built-in labels
+    let file = translations.replace("{lang}", language);
+    if file != *translations && !std::path::Path::new(&file).exists() {
+        return Ok(catalog);
+    }
+    let contents = fs::read_to_string(&file)
+        .map_err(|error| anyhow::anyhow!("Could not read '{file}': {error}"))?;
+    let unknown = catalog
+        .extend(&contents)
+        .map_err(|error| anyhow::anyhow!("Invalid '{file}': {error}"))?;
+    for key in unknown {
+        eprintln!(
+            "{}",
+            catalog.format(
+                "messages.translation_unknown",
+                &[("key", &key), ("file", &file)]
+            )
+        );
+    }
+    for kind in project.visible_kinds() {
+        let key = format!("kinds.{}", kind.name);
+        if kind.label.is_none() && catalog.find(&key).is_none() {
+            eprintln!(
+                "{}",
+                catalog.format(
+                    "messages.translation_missing",
+                    &[("key", &key), ("file", &file)]
+                )
+            );
+        }
+    }
+    Ok(catalog)
+}
+
+/// The commits chosen on the command line, the tag to record them under and its date.
+fn read_commits(
+    config: &Config,
+    pattern: Option<&str>,
+    catalog: &Catalog,
+) -> anyhow::Result<(String, Revisions, Option<ReleaseDate>)> {
+    if let CommitSource::File(file) = &config.source {
+        let contents = fs::read_to_string(file)
+            .map_err(|error| anyhow::anyhow!("Could not read '{file}': {error}"))?;
+        let revisions = Revisions {
+            from: None,
+            to: None,
+            tag: config.tag.clone(),
+        };
+        return Ok((contents, revisions, None));
+    }
+    let revisions = match &config.unreleased {
+        Some(label) => get_unreleased_revisions(label, pattern)?,
+        None => get_revisions(
+            config.from.as_deref(),
+            config.to.as_deref(),
+            pattern,
+            catalog,
+        )?,
+    };
+    let contents = read_from_git(&revisions)?;
+    let date = match config.unreleased {
+        Some(_) => None,
+        None => get_date(revisions.to.as_deref().unwrap_or("HEAD"))?,
+    };
+    Ok((contents, revisions, date))
+}
+
+/// Parses the commits of `contents`, with a warning for each one that doesn't follow
+/// the convention. Only `lint` fails on them.
+fn parse_commits(
+    contents: &str,
+    project: &ProjectConfig,
+    catalog: &Catalog,
+    progress: &ProgressBar,
+) -> Vec<parser::ParsedLine> {
+    let trackers = project.tracker_projects();
+    let (parsed_lines, problems) = parser::split_all(contents, &trackers, Some(progress));
+    for problem in problems {
+        let warning = catalog.format("messages.unparsed", &[("problem", &problem)]);
+        progress.suspend(|| eprintln!("{warning}"));
+    }
+    parsed_lines
+}
+
+/// Every problem found in the commits of `contents`, one line per commit.
+fn lint_commits(contents: &str, project: &ProjectConfig, catalog: &Catalog) -> Vec<String> {
+    let mut problems = vec![];
+    for (source, parsed) in parser::parse_all(contents) {
+        match parsed {
+            Err(error) => problems.push(format!("{source}: {error}")),
+            Ok(commit) if commit.kind == "other" && commit.title == "other" => {
+                problems.push(format!("{source}: {}", catalog.get("messages.no_type")))
+            }
+            Ok(commit)
+                if project.kind(&commit.kind).is_none() && !project.is_hidden(&commit.kind) =>
+            {
+                problems.push(format!(
+                    "{source}: {}",
+                    catalog.format("messages.unknown_type", &[("kind", &commit.kind)])
+                ))
+            }
+            Ok(_) => {}
+        }
+    }
+    problems
+}
+
+/// Checks the commits without recording them, failing when one of them is invalid.
+fn lint(config: &Config, project: &ProjectConfig, catalog: &Catalog) -> anyhow::Result<()> {
+    let (contents, _, _) = read_commits(config, project.tag_pattern.as_deref(), catalog)?;
+    let total = parser::count(&contents);
+    let problems = lint_commits(&contents, project, catalog);
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if !problems.is_empty() {
+        anyhow::bail!(catalog.format(
+            "messages.lint_failed",
+            &[("problems", &problems.len()), ("total", &total)]
+        ));
+    }
+    println!(
+        "{}",
+        catalog.format("messages.lint_passed", &[("total", &total)])
+    );
+    Ok(())
+}
+
+async fn manage_database(
+    command: &DatabaseCommand,
+    project: &ProjectConfig,
+    catalog: &Catalog,
+) -> anyhow::Result<()> {
+    if *command == DatabaseCommand::Path {
+        println!("{}", project.database);
+        return Ok(());
+    }
+    let pool = data_access::connect(&project.database).await?;
+    match command {
+        DatabaseCommand::Tags => {
+            for tag in data_access::get_tags(&pool).await? {
+                println!("{}\t{}", tag.tag, tag.commits);
+            }
         }
-        content = parts[1].trim().to_string();
-        hash = parts[2].trim().to_string();
+        DatabaseCommand::Forget(tag) => {
+            let removed = data_access::forget_tag(tag, &pool).await?;
+            if removed == 0 {
+                anyhow::bail!(catalog.format("messages.not_recorded", &[("tag", tag)]));
+            }
+            println!(
+                "{}",
+                catalog.format("messages.forgotten", &[("count", &removed), ("tag", tag)])
+            );
+        }
+        DatabaseCommand::Path => {}
+    }
+    Ok(())
+}
+
+/// Records the commits of every tag and writes one section per release, newest first.
+async fn write_changelog(
+    output: &str,
+    project: &ProjectConfig,
+    catalogs: &[Catalog],
+    renderer: &dyn Renderer,
+) -> anyhow::Result<()> {
+    let tags = get_all_tags(project.tag_pattern.as_deref())?;
+    if tags.is_empty() && !renderer.recorded_releases() {
+        anyhow::bail!(catalogs[0].get("messages.no_tag").to_string());
+    }
+
+    let mut releases: Vec<(Revisions, Option<ReleaseDate>, String)> = vec![];
+    let mut from = None;
+    for (tag, date) in &tags {
+        let revisions = Revisions {
+            from: from.replace(tag.clone()),
+            to: Some(tag.clone()),
+            tag: tag.clone(),
+        };
+        let contents = read_from_git(&revisions)?;
+        releases.push((revisions, Some(date.clone()), contents));
+    }
+
+    let size: u64 = releases
+        .iter()
+        .map(|(_, _, contents)| parser::count(contents))
+        .sum::<usize>()
+        .try_into()
+        .unwrap();
+    let outputs = localized_outputs(output, catalogs);
+    print_generating(&catalogs[0], size, &outputs);
+    let progress = get_progress_bar(size * (2 + outputs.len() as u64));
+
+    let pool = data_access::connect(&project.database).await?;
+    for (revisions, _, contents) in &releases {
+        let parsed_lines = parse_commits(contents, project, &catalogs[0], &progress);
+        data_access::record_commits(&revisions.tag, false, &pool, parsed_lines, Some(&progress))
+            .await?;
+    }
+
+    let releases: Vec<(Revisions, Option<ReleaseDate>)> = if renderer.recorded_releases() {
+        let releases = recorded_releases(&pool, &tags).await?;
+        if releases.is_empty() {
+            anyhow::bail!(catalogs[0].get("messages.no_release").to_string());
+        }
+        releases
     } else {
-        content = parts[0].trim().to_string();
-        hash = parts[1].trim().to_string();
+        releases
+            .into_iter()
+            .map(|(revisions, date, _)| (revisions, date))
+            .collect()
+    };
+
+    for (output, catalog) in &outputs {
+        let mut notes: Vec<Release> = vec![];
+        for (revisions, date) in releases.iter().rev() {
+            let date = date.clone();
+            notes.push(
+                build_release(revisions, date, project, catalog, &pool, Some(&progress)).await?,
+            );
+        }
+        write_release_note(output, renderer.render_changelog(&notes)?)?;
     }
+    progress.finish_with_message("Done");
 
-    ParsedLine {
-        kind,
-        title,
-        content,
-        hash,
+    Ok(())
+}
+
+/// Every release recorded in the database, oldest first, with the date of its git tag when
+/// it is one of `tags`.
+async fn recorded_releases(
+    pool: &SqlitePool,
+    tags: &[(String, ReleaseDate)],
+) -> anyhow::Result<Vec<(Revisions, Option<ReleaseDate>)>> {
+    let mut releases = vec![];
+    let mut from = None;
+    for tag in data_access::get_released_tags(pool).await? {
+        let date = tags
+            .iter()
+            .find(|(name, _)| *name == tag)
+            .map(|(_, date)| date.clone());
+        let revisions = Revisions {
+            from: from.replace(tag.clone()),
+            to: Some(tag.clone()),
+            tag,
+        };
+        releases.push((revisions, date));
+    }
+    Ok(releases)
+}
+
+/// The file of each language: `output` itself for a single language, otherwise
+/// `output` with the language before its extension, as in `whats_new.fr.md`.
+fn localized_outputs<'a>(output: &str, catalogs: &'a [Catalog]) -> Vec<(String, &'a Catalog)> {
+    if let [catalog] = catalogs {
+        return vec![(output.to_string(), catalog)];
+    }
+    catalogs
+        .iter()
+        .map(|catalog| (localized_path(output, catalog.language()), catalog))
+        .collect()
+}
+
+fn localized_path(output: &str, language: &str) -> String {
+    if output == STDOUT {
+        return output.to_string();
+    }
+    let path = std::path::Path::new(output);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}.{language}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{language}"),
+    };
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+/// Prints where the notes go, on the standard error when they go to the standard output.
+fn print_generating(catalog: &Catalog, size: u64, outputs: &[(String, &Catalog)]) {
+    let files: Vec<&str> = outputs.iter().map(|(output, _)| output.as_str()).collect();
+    let message = catalog.format(
+        "messages.generating",
+        &[("count", &size), ("output", &files.join("', '"))],
+    );
+    if files.contains(&STDOUT) {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
     }
 }
 
@@ -320,126 +785,289 @@ fn get_progress_bar(size: u64) -> ProgressBar {
     pb
 }
 
-fn beautify_kind(kind: &str) -> anyhow::Result<&str> {
-    let result;
-    let mut kinds: HashMap<&str, &str> = HashMap::from([
-        ("feat", "New features"),
-        ("fix", "Bug fix"),
-        ("chore", "Chore"),
-        ("refactor", "Refactoring"),
-        ("docs", "Documentation"),
-        ("style", "Code Style"),
-        ("test", "Test"),
-        ("perf", "Performances"),
-        ("ci", "Continuous Integration (CI)"),
-        ("build", "Build System"),
-        ("revert", "Reverts"),
-        ("update", "Updates"),
-        ("other", ""),
-    ]);
-
-    if env::var("LANG_FR").is_ok() {
-        kinds = HashMap::from([
-            ("feat", "Nouvelles fonctionnalités"),
-            ("fix", "Correction d'erreur"),
-            ("chore", "Chore"),
-            ("refactor", "Refactoring"),
-            ("docs", "Documentation"),
-            ("style", "Style de Code"),
-            ("test", "Test"),
-            ("perf", "Performances"),
-            ("ci", "Déploiement Continue"),
-            ("build", " Système de Build"),
-            ("revert", "Annulations"),
-            ("update", "Mise à jour"),
-            ("other", ""),
-        ]);
-    }
-
-    if kinds.contains_key(kind) {
-        result = kinds[kind];
-    } else {
-        panic!("kind {kind} not found");
+/// Heading of a kind: its emoji and its label, from the configuration when it has one.
+fn kind_label(kind: &KindConfig, catalog: &Catalog) -> String {
+    let label = match (&kind.label, catalog.find(&format!("kinds.{}", kind.name))) {
+        (Some(label), _) => label.clone(),
+        (None, Some(label)) => label.to_string(),
+        (None, None) => beautify_title(&kind.name),
+    };
+    match &kind.emoji {
+        Some(emoji) => format!("{emoji} {label}"),
+        None => label,
     }
-    Ok(result)
 }
 
 fn beautify_title(title: &str) -> String {
-    let result;
-    if title.len() <= 3 {
-        result = title.to_uppercase();
-    } else {
-        let cap = title.to_uppercase();
-        let mut res = String::from(&cap[0..1].to_string());
-        res.push_str(&title[1..]);
-        result = res;
+    match title.chars().next() {
+        Some(first) if title.len() > 3 => {
+            let mut res: String = first.to_uppercase().collect();
+            res.push_str(&title[first.len_utf8()..]);
+            res
+        }
+        _ => title.to_uppercase(),
+    }
+}
+
+/// An entry of the notes, with the co-authors found in the trailers of its commit and
+/// its references.
+async fn build_entry(commit: data_access::Commit, pool: &SqlitePool) -> anyhow::Result<Entry> {
+    let mut authors = vec![];
+    if !commit.author.is_empty() {
+        authors.push(commit.author);
+    }
+    let mut refs = vec![];
+    for footer in data_access::get_footers(&commit.hash, pool).await? {
+        if footer.token.eq_ignore_ascii_case("refs") {
+            refs.extend(footer.value.split(',').map(|r| r.trim().to_string()));
+        } else if footer.token.eq_ignore_ascii_case("co-authored-by") {
+            // `Name <email>`, only the name is kept
+            let name = footer.value.split('<').next().unwrap_or_default().trim();
+            if !name.is_empty() && !authors.iter().any(|author| author == name) {
+                authors.push(name.to_string());
+            }
+        }
     }
-    result
+    let references = data_access::get_references(&commit.hash, pool)
+        .await?
+        .into_iter()
+        .map(|reference| Reference {
+            key: reference.key,
+            closes: reference.closes,
+            url: None,
+        })
+        .collect();
+    Ok(Entry {
+        content: commit.content,
+        hash: commit.hash,
+        authors,
+        breaking: commit.breaking,
+        breaking_note: commit.breaking_note,
+        refs,
+        references,
+        url: None,
+    })
 }
 
-async fn generate_release_notes(
+/// The section of a kind, gathering the commits of every kind in `sources`.
+async fn build_section(
+    kind: &str,
+    label: &str,
+    sources: &[String],
     tag: &str,
+    catalog: &Catalog,
     pool: &SqlitePool,
     progress: Option<&ProgressBar>,
-) -> anyhow::Result<String> {
-    let order = vec![
-        "other".to_string(),
-        "feat".to_string(),
-        "fix".to_string(),
-        "update".to_string(),
-        "chore".to_string(),
-        "refactor".to_string(),
-        "docs".to_string(),
-        "style".to_string(),
-        "test".to_string(),
-        "perf".to_string(),
-        "ci".to_string(),
-        "build".to_string(),
-        "revert".to_string(),
-    ];
-    let mut notes = String::new();
-    let ki = data_access::get_kinds(tag, &pool).await?;
-    let mut kinds: Vec<String> = vec![];
-    for k in ki {
-        kinds.push(k.kind);
-    }
-
-    for kind in order {
-        if !kinds.contains(&kind) {
-            continue;
+) -> anyhow::Result<Section> {
+    let mut titles: Vec<(String, Vec<data_access::Title>)> = vec![];
+    for kind in sources {
+        for title in data_access::get_titles(tag, kind, pool).await? {
+            match titles.iter_mut().find(|(name, _)| *name == title.title) {
+                Some((_, same)) => same.push(title),
+                None => titles.push((title.title.clone(), vec![title])),
+            }
         }
-        notes.push_str(&format!("# {}\n", beautify_kind(&kind)?));
+    }
 
-        let titles = data_access::get_titles(tag, &kind, &pool).await?;
+    let mut scopes = vec![];
+    for (name, titles) in titles {
+        let label = match catalog.find(&format!("titles.{name}")) {
+            _ if name == "other" => None,
+            Some(title) => Some(title.to_string()),
+            None => Some(beautify_title(&name)),
+        };
+        let mut entries = vec![];
         for title in &titles {
-            if title.title != String::from("other") {
-                notes.push_str(&format!("## {}\n", beautify_title(&title.title)));
-            }
-
-            let commits = data_access::get_commits(tag, title, &pool).await?;
-            for commit in &commits {
-                notes.push_str(&format!("- {}\n", commit.content));
+            let commits = data_access::get_commits(tag, title, catalog.language(), pool).await?;
+            for commit in commits {
+                entries.push(build_entry(commit, pool).await?);
                 if let Some(p) = progress {
                     p.inc(1);
                 }
             }
         }
+        scopes.push(Scope {
+            name,
+            label,
+            entries,
+        });
     }
+    Ok(Section {
+        kind: kind.to_string(),
+        label: label.to_string(),
+        category: None,
+        scopes,
+    })
+}
 
-    Ok(notes)
+/// The range of `revisions`, such as `v0.1.0...HEAD`, and the page of the forge
+/// comparing them, when both ends are known.
+fn compare(links: &Links, revisions: &Revisions) -> Option<(String, String)> {
+    let from = revisions.from.as_deref()?;
+    let to = revisions.to.as_deref().unwrap_or(&revisions.tag);
+    let url = links.compare(from, to)?;
+    Some((format!("{from}...{to}"), url))
 }
 
+/// Gathers the commits recorded under the tag of `revisions` into a release, in the
+/// language of `catalog`.
+async fn build_release(
+    revisions: &Revisions,
+    date: Option<ReleaseDate>,
+    project: &ProjectConfig,
+    catalog: &Catalog,
+    pool: &SqlitePool,
+    progress: Option<&ProgressBar>,
+) -> anyhow::Result<Release> {
+    let tag = revisions.tag.as_str();
+    let links = project.links();
+    let (compare_range, compare_url) = compare(&links, revisions).unzip();
+    let mut release = Release {
+        tag: tag.to_string(),
+        previous_tag: revisions.from.clone(),
+        time: date.as_ref().map(|date| date.time),
+        date: date.map(|date| date.day),
+        language: catalog.language().to_string(),
+        breaking_label: catalog.get("kinds.breaking").to_string(),
+        more_label: catalog.get("messages.more").to_string(),
+        compare_range,
+        compare_url,
+        ..Default::default()
+    };
+
+    for commit in data_access::get_breaking(tag, catalog.language(), pool).await? {
+        if !project.is_hidden(&commit.kind) {
+            release.breaking.push(build_entry(commit, pool).await?);
+        }
+    }
+
+    let ki = data_access::get_kinds(tag, pool).await?;
+    let mut kinds: Vec<String> = vec![];
+    for k in ki {
+        kinds.push(k.kind);
+    }
+
+    let unknown: Vec<String> = kinds
+        .iter()
+        .filter(|kind| project.kind(kind).is_none() && !project.is_hidden(kind))
+        .cloned()
+        .collect();
+    if project.unknown_kinds == UnknownKinds::Fail && !unknown.is_empty() {
+        let mut message = catalog.get("messages.unknown_kinds").to_string();
+        for kind in &unknown {
+            for commit in data_access::get_kind_commits(tag, kind, pool).await? {
+                let hash = commit.hash.get(..7).unwrap_or(&commit.hash);
+                message.push_str(&format!("\n  {hash} {kind}: {}", commit.content));
+            }
+        }
+        message.push('\n');
+        message.push_str(catalog.get("messages.unknown_kinds_hint"));
+        anyhow::bail!(message);
+    }
+
+    for kind in project.visible_kinds() {
+        let mut sources: Vec<String> = vec![];
+        if kinds.contains(&kind.name) {
+            sources.push(kind.name.clone());
+        }
+        if kind.name == "other" && project.unknown_kinds == UnknownKinds::Other {
+            sources.extend(unknown.iter().cloned());
+        }
+        if sources.is_empty() {
+            continue;
+        }
+        let label = kind_label(kind, catalog);
+        let mut section =
+            build_section(&kind.name, &label, &sources, tag, catalog, pool, progress).await?;
+        section.category = kind.category;
+        release.sections.push(section);
+    }
+    if project.unknown_kinds == UnknownKinds::Raw {
+        for kind in &unknown {
+            let label = catalog.find(&format!("kinds.{kind}")).unwrap_or(kind);
+            let sources = std::slice::from_ref(kind);
+            release
+                .sections
+                .push(build_section(kind, label, sources, tag, catalog, pool, progress).await?);
+        }
+    }
+
+    let entries = release.breaking.iter_mut().chain(
+        release
+            .sections
+            .iter_mut()
+            .flat_map(|section| &mut section.scopes)
+            .flat_map(|scope| &mut scope.entries),
+    );
+    for entry in entries {
+        entry.url = links.commit(&entry.hash);
+        for reference in &mut entry.references {
+            reference.url = links.reference(&reference.key);
+        }
+    }
+    Ok(release)
+}
+
+/// Writes `notes` to `file_path`, or to the standard output when it is `-`.
 fn write_release_note(file_path: &str, notes: String) -> anyhow::Result<()> {
+    if file_path == STDOUT {
+        print!("{notes}");
+        return Ok(());
+    }
+    if let Some(directory) = std::path::Path::new(file_path).parent() {
+        fs::create_dir_all(directory)?;
+    }
     let mut file = File::create(file_path)?;
     file.write_fmt(format_args!("{}", notes))?;
     Ok(())
 }
 
+/// Adds `release` to `document`. The notes of the unreleased commits, written under
+/// one of the `labels`, are removed first, so that `--unreleased` replaces them and
+/// that the release takes their place once it is tagged.
+fn update_document(
+    document: &str,
+    renderer: &dyn Renderer,
+    release: &Release,
+    labels: &[String],
+) -> anyhow::Result<String> {
+    let mut document = document.to_string();
+    for label in labels {
+        let preview = Release {
+            tag: label.clone(),
+            ..Default::default()
+        };
+        document = renderer.remove(&document, &preview)?;
+    }
+    renderer.insert(&document, release)
+}
+
+/// Adds `release` to the changelog at `file_path`, creating it if needed.
+fn insert_release_note(
+    file_path: &str,
+    renderer: &dyn Renderer,
+    release: &Release,
+    labels: &[String],
+    catalog: &Catalog,
+) -> anyhow::Result<()> {
+    if file_path == STDOUT {
+        anyhow::bail!(catalog.get("messages.update_output").to_string());
+    }
+    let document = match fs::read_to_string(file_path) {
+        Ok(document) => document,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error.into()),
+    };
+    let document = update_document(&document, renderer, release, labels)?;
+    write_release_note(file_path, document)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::result::Result::Ok;
-    use tokio;
+    use parser::{split_all, ParsedLine};
+    use render::Markdown;
+    use std::collections::BTreeMap;
 
     #[test]
     fn split_one_work() {
@@ -450,8 +1078,9 @@ mod test {
             title: String::from("reward"),
             content: String::from("Added one more reward"),
             hash: String::from("13883a342dfe858a234d5366a855b49ddc0c534b"),
+            ..Default::default()
         };
-        assert_eq!(pars, split_one(contents));
+        assert_eq!(pars, parser::parse_line(contents).unwrap());
     }
 
     #[test]
@@ -468,44 +1097,98 @@ mod test {
                 title: String::from("reward"),
                 content: String::from("Added one more reward"),
                 hash: String::from("13883a342dfe858a234d5366a855b49ddc0c534b"),
+                ..Default::default()
             },
             ParsedLine {
                 kind: String::from("feat"),
                 title: String::from("reward"),
                 content: String::from("Added two more rewards"),
                 hash: String::from("dd187eebf6321df5b541185dd0fd110b1b384712"),
+                ..Default::default()
             },
             ParsedLine {
                 kind: String::from("update"),
                 title: String::from("other"),
                 content: String::from("Added more balance to the game"),
                 hash: String::from("9f0b66d57b97a33333681128f70396db7c2b3f53"),
+                ..Default::default()
             },
             ParsedLine {
                 kind: String::from("feat"),
                 title: String::from("tank"),
                 content: String::from("added one tank type"),
                 hash: String::from("06b9582c4a3a27a27e3a90c4444d8cc40ddf17e8"),
+                ..Default::default()
             },
             ParsedLine {
                 kind: String::from("fix"),
                 title: String::from("ci"),
                 content: String::from("fixed release notes path"),
                 hash: String::from("478faab0a38cc5eb15b36915981ed538005dc9fb"),
+                ..Default::default()
             },
         ];
 
-        assert_eq!(res, split_all(contents, None))
+        assert_eq!(res, split_all(contents, &[], None).0)
+    }
+
+    #[test]
+    fn build_reads_revisions() {
+        let args: Vec<String> = ["dedma", "--from", "v0.1.0", "notes.md", "--to", "hotfix"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let config = Config::build(&args).unwrap();
+        assert_eq!(config.from.as_deref(), Some("v0.1.0"));
+        assert_eq!(config.to.as_deref(), Some("hotfix"));
+        assert_eq!(config.output.as_deref(), Some("notes.md"));
+
+        let args = vec![String::from("dedma"), String::from("--from")];
+        assert!(Config::build(&args).is_err());
+    }
+
+    #[test]
+    fn lint_reports_every_problem() {
+        let contents = "\
+        feat (UI): added a settings screen
+        Merge branch 'main'
+        feat(ui: missing parenthesis
+        security: escape player names";
+        let problems = lint_commits(contents, &ProjectConfig::default(), &Catalog::default());
+        assert_eq!(
+            problems,
+            vec![
+                "line 2: 'Merge branch 'main'': there is no type before ':'",
+                "line 3: 'feat(ui: missing parenthesis': the scope of 'feat' is missing its closing ')'",
+                "line 4: 'security: escape player names': unknown type 'security'",
+            ]
+        );
     }
 
     async fn run_test(contents: &str) -> anyhow::Result<String> {
-        let tag = "tag";
-        let result;
-        let parsed_lines = split_all(&contents, None);
+        run_test_with(contents, &ProjectConfig::default()).await
+    }
+
+    async fn run_test_with(contents: &str, project: &ProjectConfig) -> anyhow::Result<String> {
+        run_test_in(contents, project, &Catalog::default()).await
+    }
+
+    async fn run_test_in(
+        contents: &str,
+        project: &ProjectConfig,
+        catalog: &Catalog,
+    ) -> anyhow::Result<String> {
+        let revisions = Revisions {
+            from: None,
+            to: None,
+            tag: String::from("tag"),
+        };
+        let (parsed_lines, _) = split_all(contents, &project.tracker_projects(), None);
         let pool = data_access::connect_test().await?;
-        let _ = data_access::record_commits(tag, &pool, parsed_lines, None).await?;
-        result = generate_release_notes(tag, &pool, None).await?;
-        Ok(result)
+        let _ =
+            data_access::record_commits(&revisions.tag, false, &pool, parsed_lines, None).await?;
+        let release = build_release(&revisions, None, project, catalog, &pool, None).await?;
+        Markdown.render(&release)
     }
 
     #[tokio::test]
@@ -516,15 +1199,377 @@ mod test {
         let notes = fs::read_to_string("./tests/parsed.md").expect("check output file");
 
         let res = run_test(&contents).await;
-        let result;
-        match res {
-            Ok(res) => result = res,
+        let result = match res {
+            Ok(res) => res,
             Err(error) => {
                 println!("error while testing {error}");
-                result = String::new()
+                String::new()
             }
-        }
+        };
 
         assert_eq!(result, notes)
     }
+
+    #[tokio::test]
+    async fn malformed_commits_are_kept_under_other() {
+        let contents = "\
+        feat (UI): added a settings screen :37b0781c837ad2baa6490d48817bd55485b8cee8
+        fix(): oops :7e51b36b10ef5e9311cba75417f375df891efbc0
+        fix: faster loading :0503d374ad361d2cd0efbaffc3c7616349c2afb4";
+        let (_, problems) = split_all(contents, &[], None);
+        assert_eq!(
+            problems,
+            vec!["line 2: 'fix(): oops :7e51b36b10ef5e9311cba75417f375df891efbc0': the scope of 'fix' is empty"]
+        );
+        let notes = "\
+# Other
+- fix(): oops
+# New features
+## UI
+- added a settings screen
+# Bug fix
+- faster loading
+";
+        assert_eq!(run_test(contents).await.unwrap(), notes);
+    }
+
+    #[tokio::test]
+    async fn breaking_changes_come_first() {
+        let contents = "\
+        feat!: saves use the new format :7e51b36b10ef5e9311cba75417f375df891efbc0
+        feat (UI): added a settings screen :37b0781c837ad2baa6490d48817bd55485b8cee8";
+        let notes = "\
+# Breaking changes
+- saves use the new format
+# New features
+- saves use the new format
+## UI
+- added a settings screen
+";
+        assert_eq!(run_test(contents).await.unwrap(), notes);
+    }
+
+    #[tokio::test]
+    async fn hidden_kinds_leave_the_breaking_changes() {
+        let contents = "\
+        feat!: saves use the new format :7e51b36b10ef5e9311cba75417f375df891efbc0
+        refactor(db)!: new schema :37b0781c837ad2baa6490d48817bd55485b8cee8
+        perf!: one save file :0503d374ad361d2cd0efbaffc3c7616349c2afb4";
+        let project = ProjectConfig {
+            players: true,
+            hidden: vec![String::from("perf")],
+            ..Default::default()
+        };
+        let notes = "\
+# Breaking changes
+- saves use the new format
+# New features
+- saves use the new format
+";
+        assert_eq!(run_test_with(contents, &project).await.unwrap(), notes);
+    }
+
+    #[tokio::test]
+    async fn unreleased_commits_compare_with_head() {
+        let revisions = Revisions {
+            from: Some(String::from("v0.2.0")),
+            to: Some(String::from("HEAD")),
+            tag: String::from(UNRELEASED),
+        };
+        let project = ProjectConfig {
+            repository: Some(String::from("https://github.com/owner/project")),
+            ..Default::default()
+        };
+        let (parsed_lines, _) = split_all(
+            "fix: faster loading :7e51b36b10ef5e9311cba75417f375df891efbc0",
+            &[],
+            None,
+        );
+        let pool = data_access::connect_test().await.unwrap();
+        data_access::record_commits(&revisions.tag, true, &pool, parsed_lines, None)
+            .await
+            .unwrap();
+        let release = build_release(&revisions, None, &project, &Catalog::default(), &pool, None)
+            .await
+            .unwrap();
+        let notes = Markdown.render(&release).unwrap();
+        assert!(notes.starts_with(
+            "[v0.2.0...HEAD](https://github.com/owner/project/compare/v0.2.0...HEAD)\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn only_unreleased_commits_move_to_a_new_tag() {
+        let first = "fix: first :7e51b36b10ef5e9311cba75417f375df891efbc0";
+        let second = "feat: second :37b0781c837ad2baa6490d48817bd55485b8cee8";
+        let both = format!("{first}\n{second}");
+        let pool = data_access::connect_test().await.unwrap();
+        for (tag, unreleased, contents) in [
+            ("v0.1.0", false, first),
+            (UNRELEASED, true, second),
+            ("HEAD", false, &both),
+        ] {
+            let (parsed_lines, _) = split_all(contents, &[], None);
+            data_access::record_commits(tag, unreleased, &pool, parsed_lines, None)
+                .await
+                .unwrap();
+        }
+        let tags: Vec<(String, i64)> = data_access::get_tags(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tag| (tag.tag, tag.commits))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![(String::from("v0.1.0"), 1), (String::from("HEAD"), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn feeds_hold_the_recorded_releases() {
+        let pool = data_access::connect_test().await.unwrap();
+        for (tag, unreleased, contents) in [
+            (
+                "v0.1.0",
+                false,
+                "fix: first :7e51b36b10ef5e9311cba75417f375df891efbc0",
+            ),
+            (
+                "v0.2.0",
+                false,
+                "feat: second :37b0781c837ad2baa6490d48817bd55485b8cee8",
+            ),
+            (
+                "Next",
+                true,
+                "feat: third :d1b4a6ce3e4c22f3b6dd3ec5b6bb5ba5a0b1f2c9",
+            ),
+        ] {
+            let (parsed_lines, _) = split_all(contents, &[], None);
+            data_access::record_commits(tag, unreleased, &pool, parsed_lines, None)
+                .await
+                .unwrap();
+        }
+        let date = ReleaseDate {
+            day: String::from("2024-05-01"),
+            time: 1714521600,
+        };
+        let releases: Vec<(Option<String>, String, Option<String>)> =
+            recorded_releases(&pool, &[(String::from("v0.2.0"), date)])
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(revisions, date)| (revisions.from, revisions.tag, date.map(|date| date.day)))
+                .collect();
+        assert_eq!(
+            releases,
+            vec![
+                (None, String::from("v0.1.0"), None),
+                (
+                    Some(String::from("v0.1.0")),
+                    String::from("v0.2.0"),
+                    Some(String::from("2024-05-01"))
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn references_are_linked() {
+        let contents = "\
+        fix: UTF-8 names in ABC-7 (#12) :7e51b36b10ef5e9311cba75417f375df891efbc0
+        feat: XYZ-3 prices :37b0781c837ad2baa6490d48817bd55485b8cee8";
+        let project = ProjectConfig {
+            repository: Some(String::from("https://github.com/owner/project")),
+            trackers: BTreeMap::from([(
+                String::from("ABC"),
+                String::from("https://example.atlassian.net/browse/{key}"),
+            )]),
+            ..Default::default()
+        };
+        let notes = "\
+# New features
+- XYZ-3 prices ([37b0781](https://github.com/owner/project/commit/37b0781c837ad2baa6490d48817bd55485b8cee8))
+# Bug fix
+- UTF-8 names in ABC-7 ([ABC-7](https://example.atlassian.net/browse/ABC-7), [#12](https://github.com/owner/project/issues/12), [7e51b36](https://github.com/owner/project/commit/7e51b36b10ef5e9311cba75417f375df891efbc0))
+";
+        assert_eq!(run_test_with(contents, &project).await.unwrap(), notes);
+    }
+
+    #[tokio::test]
+    async fn unknown_kinds() {
+        let contents = "\
+        security: escape player names :37b0781c837ad2baa6490d48817bd55485b8cee8
+        feat: added a settings screen :7e51b36b10ef5e9311cba75417f375df891efbc0";
+        let mut project = ProjectConfig::default();
+
+        let notes = "# Other\n- escape player names\n# New features\n- added a settings screen\n";
+        assert_eq!(run_test_with(contents, &project).await.unwrap(), notes);
+
+        project.unknown_kinds = UnknownKinds::Raw;
+        let notes =
+            "# New features\n- added a settings screen\n# security\n- escape player names\n";
+        assert_eq!(run_test_with(contents, &project).await.unwrap(), notes);
+
+        project.unknown_kinds = UnknownKinds::Fail;
+        let error = run_test_with(contents, &project).await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("37b0781 security: escape player names"));
+    }
+
+    #[tokio::test]
+    async fn translations_replace_labels() {
+        let contents = "\
+        feat (UI): added a settings screen :37b0781c837ad2baa6490d48817bd55485b8cee8
+        fix!: saves are kept :7e51b36b10ef5e9311cba75417f375df891efbc0";
+        let mut catalog = Catalog::default();
+        catalog
+            .extend(
+                "[kinds]\nfeat = \"What's new\"\nbreaking = \"Read this first\"\n\n[titles]\nui = \"User interface\"",
+            )
+            .unwrap();
+        let notes = "\
+# Read this first
+- saves are kept
+# What's new
+## User interface
+- added a settings screen
+# Bug fix
+- saves are kept
+";
+        let result = run_test_in(contents, &ProjectConfig::default(), &catalog).await;
+        assert_eq!(result.unwrap(), notes);
+    }
+
+    #[test]
+    fn unreleased_notes_are_replaced() {
+        let release = |tag: &str, content: &str| Release {
+            tag: tag.to_string(),
+            sections: vec![Section {
+                label: String::from("Bug fix"),
+                scopes: vec![Scope {
+                    entries: vec![Entry {
+                        content: content.to_string(),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let changelog = Markdown
+            .render_changelog(&[release("v0.1.0", "first fix")])
+            .unwrap();
+        let preview = |document: &str, content: &str| {
+            update_document(
+                document,
+                &Markdown,
+                &release(UNRELEASED, content),
+                &[String::from(UNRELEASED)],
+            )
+        };
+
+        let changelog = preview(&changelog, "second fix").unwrap();
+        let changelog = preview(&changelog, "second and third fixes").unwrap();
+        assert_eq!(
+            changelog,
+            "<!-- dedma -->\n# Unreleased\n## Bug fix\n- second and third fixes\n\n# v0.1.0\n## Bug fix\n- first fix\n"
+        );
+
+        let changelog = update_document(
+            &changelog,
+            &Markdown,
+            &release("v0.2.0", "second and third fixes"),
+            &[String::from(UNRELEASED)],
+        )
+        .unwrap();
+        assert_eq!(
+            changelog,
+            "<!-- dedma -->\n# v0.2.0\n## Bug fix\n- second and third fixes\n\n# v0.1.0\n## Bug fix\n- first fix\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_unreleased_labels_are_removed() {
+        let contents = "fix: saves are kept :7e51b36b10ef5e9311cba75417f375df891efbc0";
+        let pool = data_access::connect_test().await.unwrap();
+        let (parsed_lines, _) = split_all(contents, &[], None);
+        data_access::record_commits("Next", true, &pool, parsed_lines, None)
+            .await
+            .unwrap();
+        let release = |tag: &str| Release {
+            tag: tag.to_string(),
+            sections: vec![Section {
+                label: String::from("Bug fix"),
+                scopes: vec![Scope {
+                    entries: vec![Entry {
+                        content: String::from("saves are kept"),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let changelog =
+            update_document("", &Markdown, &release("Next"), &[String::from("Next")]).unwrap();
+        assert!(changelog.contains("# Next\n"));
+
+        // Tagged, then added without --unreleased-label
+        let mut labels = data_access::get_unreleased_tags(&pool).await.unwrap();
+        assert_eq!(labels, vec![String::from("Next")]);
+        labels.push(String::from(UNRELEASED));
+        let changelog =
+            update_document(&changelog, &Markdown, &release("v0.2.0"), &labels).unwrap();
+        assert_eq!(
+            changelog,
+            "<!-- dedma -->\n# v0.2.0\n## Bug fix\n- saves are kept\n"
+        );
+    }
+
+    #[test]
+    fn beautify_title_work() {
+        assert_eq!(beautify_title("ui"), "UI");
+        assert_eq!(beautify_title("reward"), "Reward");
+        assert_eq!(beautify_title("économie"), "Économie");
+    }
+
+    #[test]
+    fn localized_path_work() {
+        assert_eq!(localized_path("whats_new.md", "fr"), "whats_new.fr.md");
+        assert_eq!(localized_path("docs/notes", "de"), "docs/notes.de");
+        let catalogs = [Catalog::new("en")];
+        assert_eq!(localized_outputs("notes.md", &catalogs)[0].0, "notes.md");
+    }
+
+    #[tokio::test]
+    async fn changelog_trailers_translate_commits() {
+        let contents = "\
+13883a342dfe858a234d5366a855b49ddc0c534b\x1ffeat(ui)!: added a settings screen
+
+Changelog-fr: ajout d'un écran de réglages
+\x1e
+dd187eebf6321df5b541185dd0fd110b1b384712\x1ffix: saves are kept
+\x1e
+";
+        let project = ProjectConfig::default();
+        let notes = "\
+# Changements majeurs
+- ajout d'un écran de réglages
+# Nouvelles fonctionnalités
+## UI
+- ajout d'un écran de réglages
+# Correction d'erreur
+- saves are kept
+";
+        let result = run_test_in(contents, &project, &Catalog::new("fr")).await;
+        assert_eq!(result.unwrap(), notes);
+
+        let result = run_test_in(contents, &project, &Catalog::new("en")).await;
+        assert!(result.unwrap().contains("- added a settings screen\n"));
+    }
 }
@@ -0,0 +1,213 @@
+use std::{collections::HashMap, env, fmt::Display};
+
+/// Catalogs shipped with dedma. English comes first: it fills in the messages
+/// missing from the other catalogs.
+const CATALOGS: [(&str, &str); 4] = [
+    ("en", include_str!("../locales/en.toml")),
+    ("fr", include_str!("../locales/fr.toml")),
+    ("es", include_str!("../locales/es.toml")),
+    ("de", include_str!("../locales/de.toml")),
+];
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The messages of one language, by `section.key`, such as `kinds.feat`.
+/// A language without a built-in catalog keeps its name but gets the English messages.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    language: String,
+    messages: HashMap<String, String>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Catalog::new(DEFAULT_LANGUAGE)
+    }
+}
+
+impl Catalog {
+    /// The catalog of `language`, completed with the English messages it lacks.
+    pub fn new(language: &str) -> Catalog {
+        let mut messages = HashMap::new();
+        for (name, contents) in CATALOGS {
+            if name == DEFAULT_LANGUAGE || name == language {
+                messages.extend(flatten(contents).unwrap_or_default());
+            }
+        }
+        Catalog {
+            language: language.to_string(),
+            messages,
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// The message `key`, or the key itself when no catalog has it.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    /// The message `key`, if there is one.
+    pub fn find(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    /// Puts the messages of a translation file over those of the catalog. The file has
+    /// the sections of the built-in catalogs, along with `titles` for the scopes.
+    /// Returns the keys of the file that dedma does not know, most likely typos.
+    pub fn extend(&mut self, contents: &str) -> Result<Vec<String>, toml::de::Error> {
+        let english = flatten(CATALOGS[0].1).unwrap_or_default();
+        let mut unknown = vec![];
+        for (key, message) in flatten(contents)? {
+            let known = key.starts_with("kinds.")
+                || key.starts_with("titles.")
+                || english.contains_key(&key);
+            if !known {
+                unknown.push(key.clone());
+            }
+            self.messages.insert(key, message);
+        }
+        unknown.sort();
+        Ok(unknown)
+    }
+
+    /// The message `key` with every `{name}` replaced by its value in `args`.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut message = self.get(key).to_string();
+        for (name, value) in args {
+            message = message.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+        message
+    }
+}
+
+/// Reads a catalog into `section.key` pairs. The shipped catalogs are checked by the tests.
+fn flatten(contents: &str) -> Result<HashMap<String, String>, toml::de::Error> {
+    let mut messages = HashMap::new();
+    for (section, values) in contents.parse::<toml::Table>()? {
+        if let toml::Value::Table(values) = values {
+            for (key, value) in values {
+                if let toml::Value::String(value) = value {
+                    messages.insert(format!("{section}.{key}"), value);
+                }
+            }
+        }
+    }
+    Ok(messages)
+}
+
+pub fn is_supported(language: &str) -> bool {
+    CATALOGS.iter().any(|(name, _)| *name == language)
+}
+
+/// Reduces a locale such as `fr_FR.UTF-8` or `de-DE` to its language, `fr` or `de`.
+/// `C` and `POSIX` have no language.
+pub fn language_of(locale: &str) -> Option<String> {
+    let language = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    if language.is_empty() || language == "c" || language == "posix" {
+        return None;
+    }
+    Some(language)
+}
+
+/// The languages of the notes: those of `requested`, a comma-separated list from `--lang`
+/// or the configuration, when given, otherwise the first of `LC_ALL`, `LC_MESSAGES` and
+/// `LANG` that is set. A language may have no catalog, `Catalog::new` then uses English.
+pub fn resolve(requested: Option<&str>) -> Vec<String> {
+    let mut languages: Vec<String> = vec![];
+    for language in requested
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(language_of)
+    {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    if !languages.is_empty() {
+        return languages;
+    }
+    let language = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|variable| env::var(variable).ok())
+        .find_map(|locale| language_of(&locale))
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    vec![language]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn catalogs_have_every_message() {
+        let english = flatten(CATALOGS[0].1).unwrap();
+        assert!(english.contains_key("kinds.feat"));
+        for (name, contents) in CATALOGS {
+            let messages = flatten(contents).unwrap();
+            for key in english.keys() {
+                assert!(
+                    messages.contains_key(key),
+                    "'{key}' is missing from '{name}'"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn translation_files_override_the_catalog() {
+        let mut catalog = Catalog::new("fr");
+        let unknown = catalog
+            .extend(
+                "
+[kinds]
+feat = \"Quoi de neuf\"
+security = \"Sécurité\"
+
+[titles]
+ui = \"Interface\"
+
+[mesages]
+generating = \"typo\"
+",
+            )
+            .unwrap();
+        assert_eq!(unknown, vec!["mesages.generating"]);
+        assert_eq!(catalog.get("kinds.feat"), "Quoi de neuf");
+        assert_eq!(catalog.get("kinds.fix"), "Correction d'erreur");
+        assert_eq!(catalog.get("titles.ui"), "Interface");
+        assert!(catalog.extend("[kinds\nfeat = 1").is_err());
+    }
+
+    #[test]
+    fn locales_are_reduced_to_languages() {
+        assert_eq!(language_of("fr_FR.UTF-8").as_deref(), Some("fr"));
+        assert_eq!(language_of("de-DE").as_deref(), Some("de"));
+        assert_eq!(language_of("ES").as_deref(), Some("es"));
+        assert_eq!(language_of("C.UTF-8"), None);
+        assert_eq!(resolve(Some("es_MX")), vec!["es"]);
+        assert_eq!(resolve(Some("en, fr,en")), vec!["en", "fr"]);
+    }
+
+    #[test]
+    fn unknown_languages_fall_back_to_english() {
+        let catalog = Catalog::new("it");
+        assert_eq!(catalog.language(), "it");
+        assert_eq!(catalog.get("kinds.feat"), "New features");
+
+        let catalog = Catalog::new("de");
+        assert_eq!(catalog.get("kinds.feat"), "Neue Funktionen");
+        assert_eq!(catalog.get("kinds.unknown"), "kinds.unknown");
+        assert_eq!(
+            catalog.format("messages.lint_passed", &[("total", &3)]),
+            "3 Commits geprüft"
+        );
+    }
+}
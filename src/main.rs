@@ -1,14 +1,14 @@
 use dedma::Config;
 use std::{env, process};
-use tokio;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     let args: Vec<String> = env::args().collect();
-    let config = Config::build(&args);
+    let config = Config::build(&args).unwrap_or_else(|error| error.exit());
 
-    let _ = dedma::run(config).await.unwrap_or_else(|error| {
-        println!("Application error : {error}");
+    let error_label = config.error_label();
+    dedma::run(config).await.unwrap_or_else(|error| {
+        println!("{error_label} : {error}");
         process::exit(1)
     });
 }
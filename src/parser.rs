@@ -0,0 +1,720 @@
+use indicatif::ProgressBar;
+use std::fmt;
+
+/// A trailer at the end of a commit message, such as `Reviewed-by: Z` or `Refs #133`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Footer {
+    pub token: String,
+    pub value: String,
+}
+
+/// An issue or pull request a commit refers to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reference {
+    /// `#123`, `owner/project#123`, or the key of another tracker such as `ABC-123`.
+    pub key: String,
+    /// Closed by the commit, as in `Closes #45`.
+    pub closes: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ParsedLine {
+    pub kind: String,
+    pub title: String,
+    pub content: String,
+    pub hash: String,
+    /// Name of the author, only known for the commits read from git.
+    pub author: String,
+    pub body: String,
+    pub footers: Vec<Footer>,
+    pub breaking: bool,
+    /// Migration text from the `BREAKING CHANGE:` footer, empty for a bare `!`.
+    pub breaking_note: String,
+    /// The references found in the subject, the body and the trailers.
+    pub references: Vec<Reference>,
+}
+
+/// Why a commit message could not be read as a Conventional Commit.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    Empty,
+    EmptyType,
+    UnclosedScope(String),
+    EmptyScope(String),
+    EmptyDescription(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "the commit message is empty"),
+            ParseError::EmptyType => write!(f, "the type before ':' is empty"),
+            ParseError::UnclosedScope(kind) => {
+                write!(f, "the scope of '{kind}' is missing its closing ')'")
+            }
+            ParseError::EmptyScope(kind) => write!(f, "the scope of '{kind}' is empty"),
+            ParseError::EmptyDescription(kind) => {
+                write!(f, "the description after '{kind}:' is empty")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Header {
+    kind: String,
+    title: String,
+    content: String,
+    breaking: bool,
+}
+
+/// Separates two commits in the output of `read_from_git`.
+pub const RECORD_SEPARATOR: char = '\x1e';
+/// Separates the hash, the author and the message inside a record.
+pub const FIELD_SEPARATOR: char = '\x1f';
+
+/// Number of commits in `contents`, used to size the progress bar.
+pub fn count(contents: &str) -> usize {
+    if contents.contains(RECORD_SEPARATOR) {
+        contents
+            .split(RECORD_SEPARATOR)
+            .filter(|record| !record.trim().is_empty())
+            .count()
+    } else {
+        contents.lines().count()
+    }
+}
+
+/// One commit of the input, before its message is parsed.
+struct Source<'a> {
+    /// The commit as `line N: 'subject'` or `commit hash: 'subject'`, for the messages.
+    name: String,
+    message: &'a str,
+    hash: String,
+    /// Empty for the commits read from a file.
+    author: &'a str,
+}
+
+impl Source<'_> {
+    fn parse(&self, trackers: &[&str]) -> Result<ParsedLine, ParseError> {
+        parse_commit(self.message, &self.hash, trackers).map(|parsed| ParsedLine {
+            author: self.author.to_string(),
+            ..parsed
+        })
+    }
+
+    /// The commit under `other`, its subject kept whole as the description.
+    fn unparsed(&self) -> ParsedLine {
+        ParsedLine {
+            kind: String::from("other"),
+            title: String::from("other"),
+            content: self
+                .message
+                .trim()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            hash: self.hash.clone(),
+            author: self.author.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parses every commit of `contents`: either full messages in `hash US author US message RS`
+/// records as written by `read_from_git`, or one `subject :hash` per line.
+///
+/// The commits that don't follow the convention are kept under `other`, the problem
+/// with each of them is returned next to the commits. The keys such as `ABC-123` are
+/// references only for the projects of `trackers`.
+pub fn split_all(
+    contents: &str,
+    trackers: &[&str],
+    progress: Option<&ProgressBar>,
+) -> (Vec<ParsedLine>, Vec<String>) {
+    let mut res: Vec<ParsedLine> = Vec::new();
+    let mut problems = vec![];
+    for source in sources(contents) {
+        match source.parse(trackers) {
+            Ok(parsed) => res.push(parsed),
+            Err(error) => {
+                problems.push(format!("{}: {error}", source.name));
+                // An empty message has nothing to show
+                if error != ParseError::Empty {
+                    res.push(source.unparsed());
+                }
+            }
+        }
+        if let Some(p) = progress {
+            p.inc(1);
+        }
+    }
+    (res, problems)
+}
+
+/// Parses every commit of `contents` without stopping at the first error. Each result
+/// comes with the commit it is about, as `line N: 'subject'` or `commit hash: 'subject'`.
+pub fn parse_all(contents: &str) -> Vec<(String, Result<ParsedLine, ParseError>)> {
+    sources(contents)
+        .into_iter()
+        .map(|source| {
+            // The references don't matter to the checks
+            let parsed = source.parse(&[]);
+            (source.name, parsed)
+        })
+        .collect()
+}
+
+fn sources(contents: &str) -> Vec<Source<'_>> {
+    if contents.contains(RECORD_SEPARATOR) {
+        return records(contents);
+    }
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| line_source(number + 1, line))
+        .collect()
+}
+
+fn records(contents: &str) -> Vec<Source<'_>> {
+    let mut res = vec![];
+    for record in contents.split(RECORD_SEPARATOR) {
+        let record = record.trim();
+        if record.is_empty() {
+            continue;
+        }
+        let (hash, message) = match record.split_once(FIELD_SEPARATOR) {
+            Some((hash, message)) => (hash.trim().to_string(), message),
+            None => (synthetic_hash(record), record),
+        };
+        // The author is missing from the records written before it was added
+        let (author, message) = match message.split_once(FIELD_SEPARATOR) {
+            Some((author, message)) => (author.trim(), message),
+            None => ("", message),
+        };
+        let subject = message.trim().lines().next().unwrap_or_default();
+        let short = hash.get(..7).unwrap_or(&hash);
+        res.push(Source {
+            name: format!("commit {short}: '{subject}'"),
+            message,
+            hash,
+            author,
+        });
+    }
+    res
+}
+
+/// The commit of a `subject :hash` line. The hash is optional; when it is
+/// missing a stable one is derived from the subject.
+fn line_source(number: usize, line: &str) -> Source<'_> {
+    let line = line.trim();
+    let (subject, hash) = split_hash(line);
+    Source {
+        name: format!("line {number}: '{line}'"),
+        message: subject,
+        hash: match hash {
+            Some(hash) => hash.to_string(),
+            None => synthetic_hash(subject),
+        },
+        author: "",
+    }
+}
+
+/// Parses a single `subject :hash` line.
+#[cfg(test)]
+pub fn parse_line(line: &str) -> Result<ParsedLine, ParseError> {
+    line_source(1, line).parse(&[])
+}
+
+/// Parses a full commit message: a `type(scope)!: description` header,
+/// an optional body and optional footers, each separated by a blank line.
+pub fn parse_commit(
+    message: &str,
+    hash: &str,
+    trackers: &[&str],
+) -> Result<ParsedLine, ParseError> {
+    let message = message.trim();
+    let (header, rest) = match message.split_once('\n') {
+        Some((header, rest)) => (header.trim(), rest),
+        None => (message, ""),
+    };
+    if header.is_empty() {
+        return Err(ParseError::Empty);
+    }
+
+    let header = parse_header(header)?;
+    let (body, footers) = parse_body(rest);
+    let breaking_note = footers
+        .iter()
+        .filter(|footer| is_breaking_token(&footer.token))
+        .map(|footer| footer.value.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    let mut references = vec![];
+    find_references(&header.content, trackers, &mut references);
+    find_references(&body, trackers, &mut references);
+    for footer in &footers {
+        // `Refs #133` is split into the `Refs` token and `133`
+        let value = if footer.value.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("#{}", footer.value)
+        } else {
+            footer.value.clone()
+        };
+        find_references(
+            &format!("{}: {value}", footer.token),
+            trackers,
+            &mut references,
+        );
+    }
+
+    Ok(ParsedLine {
+        kind: header.kind,
+        title: header.title,
+        content: strip_references(&header.content).to_string(),
+        hash: hash.to_string(),
+        body,
+        breaking: header.breaking || footers.iter().any(|f| is_breaking_token(&f.token)),
+        breaking_note,
+        footers,
+        references,
+        ..Default::default()
+    })
+}
+
+/// Words announcing that a commit closes the issue that follows.
+const CLOSING_WORDS: [&str; 9] = [
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+];
+
+/// Name of an owner or a project on a forge.
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// The key of the reference written as `word`: `#123`, `owner/project#123`, `GH-123`
+/// which is `#123`, or a key such as `ABC-123` when `ABC` is one of the `trackers`,
+/// so that words such as `UTF-8` are left alone.
+fn reference_key(word: &str, trackers: &[&str]) -> Option<String> {
+    let number = |digits: &str| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+    if let Some((project, digits)) = word.split_once('#') {
+        let project =
+            project.is_empty() || project.contains('/') && project.split('/').all(is_name);
+        return (project && number(digits)).then(|| word.to_string());
+    }
+    let (prefix, digits) = word.rsplit_once('-')?;
+    if !number(digits) {
+        return None;
+    }
+    if prefix == "GH" {
+        return Some(format!("#{digits}"));
+    }
+    tracker(word)
+        .filter(|project| trackers.contains(project))
+        .map(|_| word.to_string())
+}
+
+/// The project of a key of a tracker such as Jira: `ABC` for `ABC-123`.
+pub fn tracker(key: &str) -> Option<&str> {
+    let (prefix, digits) = key.split_once('-')?;
+    let project = prefix.len() >= 2
+        && prefix.starts_with(|c: char| c.is_ascii_uppercase())
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    let number = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit());
+    (project && number).then_some(prefix)
+}
+
+/// Adds the references of `text` to `references`, those already there only once.
+fn find_references(text: &str, trackers: &[&str], references: &mut Vec<Reference>) {
+    let mut closing = false;
+    let words = text.split(|c: char| !(c.is_ascii_alphanumeric() || "#/-_.".contains(c)));
+    for word in words.map(|word| word.trim_matches('.')) {
+        if word.is_empty() {
+            continue;
+        }
+        let Some(key) = reference_key(word, trackers) else {
+            closing = CLOSING_WORDS.contains(&word.to_lowercase().as_str());
+            continue;
+        };
+        match references.iter_mut().find(|known| known.key == key) {
+            Some(known) => known.closes |= closing,
+            None => references.push(Reference {
+                key,
+                closes: closing,
+            }),
+        }
+        closing = false;
+    }
+}
+
+/// The description without the `(#123)` that squash merges put at its end.
+fn strip_references(description: &str) -> &str {
+    let Some(rest) = description.strip_suffix(')') else {
+        return description;
+    };
+    let Some((text, inside)) = rest.rsplit_once('(') else {
+        return description;
+    };
+    let pull_requests = inside
+        .split(',')
+        .all(|word| reference_key(word.trim(), &[]).is_some_and(|key| key.contains('#')));
+    match text.trim_end() {
+        text if pull_requests && !text.is_empty() => text,
+        _ => description,
+    }
+}
+
+/// Splits the trailing `:hash` off a line, if there is one.
+fn split_hash(line: &str) -> (&str, Option<&str>) {
+    if let Some((subject, hash)) = line.rsplit_once(':') {
+        let hash = hash.trim();
+        if (7..=64).contains(&hash.len()) && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return (subject.trim(), Some(hash));
+        }
+    }
+    (line.trim(), None)
+}
+
+/// FNV-1a, so the same subject always gets the same hash.
+fn synthetic_hash(subject: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in subject.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn is_breaking_token(token: &str) -> bool {
+    token == "BREAKING CHANGE" || token == "BREAKING-CHANGE"
+}
+
+fn is_type(word: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn parse_header(header: &str) -> Result<Header, ParseError> {
+    let other = Header {
+        kind: String::from("other"),
+        title: String::from("other"),
+        content: header.to_string(),
+        breaking: false,
+    };
+    let Some((prefix, description)) = header.split_once(':') else {
+        return Ok(other);
+    };
+    let prefix = prefix.trim();
+    if prefix.is_empty() {
+        return Err(ParseError::EmptyType);
+    }
+
+    let (kind, title, breaking) = match prefix.split_once('(') {
+        Some((kind, scope)) => {
+            let kind = kind.trim();
+            if !is_type(kind) {
+                return Ok(other);
+            }
+            let Some((scope, after)) = scope.split_once(')') else {
+                return Err(ParseError::UnclosedScope(kind.to_string()));
+            };
+            let after = after.trim();
+            if !after.is_empty() && after != "!" {
+                return Ok(other);
+            }
+            let scope = scope.trim();
+            if scope.is_empty() {
+                return Err(ParseError::EmptyScope(kind.to_string()));
+            }
+            (kind, scope.to_lowercase(), after == "!")
+        }
+        None => {
+            let kind = prefix.strip_suffix('!').unwrap_or(prefix).trim();
+            if !is_type(kind) {
+                return Ok(other);
+            }
+            (kind, String::from("other"), prefix.ends_with('!'))
+        }
+    };
+
+    let content = description.trim();
+    if content.is_empty() {
+        return Err(ParseError::EmptyDescription(prefix.to_string()));
+    }
+
+    Ok(Header {
+        kind: kind.to_lowercase(),
+        title,
+        content: content.to_string(),
+        breaking,
+    })
+}
+
+/// Splits a footer line into its token and value, as in `Token: value` or `Token #value`.
+fn split_footer(line: &str) -> Option<Footer> {
+    let (token, value) = if let Some(rest) = line.strip_prefix("BREAKING CHANGE:") {
+        ("BREAKING CHANGE", rest)
+    } else if let Some((token, value)) = line.split_once(": ") {
+        (token, value)
+    } else if let Some((token, value)) = line.split_once(" #") {
+        (token, value)
+    } else {
+        return None;
+    };
+    if !is_breaking_token(token) && !is_type(token) {
+        return None;
+    }
+    Some(Footer {
+        token: token.to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+fn parse_body(rest: &str) -> (String, Vec<Footer>) {
+    let mut paragraphs: Vec<Vec<&str>> = vec![];
+    let mut current: Vec<&str> = vec![];
+    for line in rest.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current);
+                current = vec![];
+            }
+        } else {
+            current.push(line.trim_end());
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+
+    let mut footers: Vec<Footer> = vec![];
+    if let Some(last) = paragraphs.last() {
+        if split_footer(last[0]).is_some() {
+            for line in last {
+                match split_footer(line) {
+                    Some(footer) => footers.push(footer),
+                    None => {
+                        let footer = footers.last_mut().expect("first line is a footer");
+                        footer.value.push('\n');
+                        footer.value.push_str(line.trim());
+                    }
+                }
+            }
+            paragraphs.pop();
+        }
+    }
+
+    let body = paragraphs
+        .iter()
+        .map(|p| p.join("\n"))
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    (body, footers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn description_may_contain_colons() {
+        let parsed =
+            parse_line("fix: handle http://x:80 urls :478faab0a38cc5eb15b36915981ed538005dc9fb")
+                .unwrap();
+        assert_eq!(parsed.kind, "fix");
+        assert_eq!(parsed.content, "handle http://x:80 urls");
+        assert_eq!(parsed.hash, "478faab0a38cc5eb15b36915981ed538005dc9fb");
+    }
+
+    #[test]
+    fn missing_hash_is_derived_from_subject() {
+        let first = parse_line("feat (UI): added a settings screen").unwrap();
+        let second = parse_line("feat (UI): added a settings screen").unwrap();
+        assert_eq!(first.content, "added a settings screen");
+        assert_eq!(first.hash.len(), 16);
+        assert_eq!(first.hash, second.hash);
+    }
+
+    #[test]
+    fn free_text_subject_is_other() {
+        let parsed =
+            parse_line("Merge branch 'main': sync :0503d374ad361d2cd0efbaffc3c7616349c2afb4")
+                .unwrap();
+        assert_eq!(parsed.kind, "other");
+        assert_eq!(parsed.title, "other");
+        assert_eq!(parsed.content, "Merge branch 'main': sync");
+    }
+
+    #[test]
+    fn malformed_headers_are_errors() {
+        assert_eq!(parse_commit("", "h", &[]), Err(ParseError::Empty));
+        assert_eq!(
+            parse_commit(": nothing", "h", &[]),
+            Err(ParseError::EmptyType)
+        );
+        assert_eq!(
+            parse_commit("feat(ui: screen", "h", &[]),
+            Err(ParseError::UnclosedScope(String::from("feat")))
+        );
+        assert_eq!(
+            parse_commit("feat(): screen", "h", &[]),
+            Err(ParseError::EmptyScope(String::from("feat")))
+        );
+        assert_eq!(
+            parse_commit("feat(ui):  ", "h", &[]),
+            Err(ParseError::EmptyDescription(String::from("feat(ui)")))
+        );
+    }
+
+    #[test]
+    fn body_and_footers() {
+        let message = "\
+feat(lang)!: add Polish language
+
+Polish is now one of the supported languages.
+It is selected with LANG_PL.
+
+Reviewed-by: Z
+Refs #133
+BREAKING CHANGE: the `lang` option
+  now expects a locale code";
+        let parsed = parse_commit(message, "abc1234", &[]).unwrap();
+        assert!(parsed.breaking);
+        assert_eq!(
+            parsed.breaking_note,
+            "the `lang` option\nnow expects a locale code"
+        );
+        assert_eq!(parsed.kind, "feat");
+        assert_eq!(parsed.title, "lang");
+        assert_eq!(parsed.content, "add Polish language");
+        assert_eq!(
+            parsed.body,
+            "Polish is now one of the supported languages.\nIt is selected with LANG_PL."
+        );
+        assert_eq!(
+            parsed.footers,
+            vec![
+                Footer {
+                    token: String::from("Reviewed-by"),
+                    value: String::from("Z"),
+                },
+                Footer {
+                    token: String::from("Refs"),
+                    value: String::from("133"),
+                },
+                Footer {
+                    token: String::from("BREAKING CHANGE"),
+                    value: String::from("the `lang` option\nnow expects a locale code"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn references_are_found() {
+        let message = "\
+fix(shop): prices GH-7 and UTF-8 names (#123)
+
+Closes #45. See owner/project#9 and ABC-123, not page#3.
+
+Fixes: ABC-123
+Refs #133, RFC 42";
+        let parsed = parse_commit(message, "h", &["ABC"]).unwrap();
+        assert_eq!(parsed.content, "prices GH-7 and UTF-8 names");
+        let references: Vec<(&str, bool)> = parsed
+            .references
+            .iter()
+            .map(|reference| (reference.key.as_str(), reference.closes))
+            .collect();
+        assert_eq!(
+            references,
+            vec![
+                ("#7", false),
+                ("#123", false),
+                ("#45", true),
+                ("owner/project#9", false),
+                ("ABC-123", true),
+                ("#133", false),
+            ]
+        );
+        assert!(!references
+            .iter()
+            .any(|(key, _)| *key == "UTF-8" || *key == "RFC 42"));
+        assert!(parse_commit("fix: ABC-123 names in UTF-8", "h", &[])
+            .unwrap()
+            .references
+            .is_empty());
+        assert_eq!(tracker("ABC-123"), Some("ABC"));
+        assert_eq!(tracker("#123"), None);
+
+        let parsed = parse_commit("feat: pricing (see #12)", "h", &[]).unwrap();
+        assert_eq!(parsed.content, "pricing (see #12)");
+        assert_eq!(parse_commit("(#12)", "h", &[]).unwrap().content, "(#12)");
+    }
+
+    #[test]
+    fn breaking_markers() {
+        let bang =
+            parse_line("feat!: drop the old database :13883a342dfe858a234d5366a855b49ddc0c534b")
+                .unwrap();
+        assert_eq!(bang.kind, "feat");
+        assert!(bang.breaking);
+        assert_eq!(bang.breaking_note, "");
+
+        let scoped = parse_commit("refactor(db)!: rename columns", "h", &[]).unwrap();
+        assert_eq!(scoped.kind, "refactor");
+        assert_eq!(scoped.title, "db");
+        assert!(scoped.breaking);
+
+        let footer = parse_commit(
+            "fix: new path\n\nBREAKING-CHANGE: move `.dedma` to `.cache`",
+            "h",
+            &[],
+        )
+        .unwrap();
+        assert!(footer.breaking);
+        assert_eq!(footer.breaking_note, "move `.dedma` to `.cache`");
+
+        assert!(
+            !parse_commit("fix: nothing special", "h", &[])
+                .unwrap()
+                .breaking
+        );
+    }
+
+    #[test]
+    fn split_git_records() {
+        let contents = "\
+13883a342dfe858a234d5366a855b49ddc0c534b\x1ffeat (Reward): Added one more reward
+
+The reward is given after each wave.
+
+Refs: #12
+\x1e
+dd187eebf6321df5b541185dd0fd110b1b384712\x1fAda Lovelace\x1fupdate: Added more balance to the game
+\x1e
+";
+        let (parsed, problems) = split_all(contents, &[], None);
+        assert!(problems.is_empty());
+        assert_eq!(count(contents), 2);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].hash, "13883a342dfe858a234d5366a855b49ddc0c534b");
+        assert_eq!(parsed[0].title, "reward");
+        assert_eq!(parsed[0].body, "The reward is given after each wave.");
+        assert_eq!(parsed[0].footers[0].token, "Refs");
+        assert_eq!(parsed[0].author, "");
+        assert_eq!(parsed[1].kind, "update");
+        assert_eq!(parsed[1].author, "Ada Lovelace");
+        assert_eq!(parsed[1].body, "");
+    }
+}
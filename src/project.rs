@@ -0,0 +1,411 @@
+use crate::forge::{Forge, Links};
+use crate::render::{Category, Feed, Package};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Name of the project configuration file, looked up at the root of the repository.
+pub const CONFIG_FILE: &str = ".dedma.toml";
+
+/// Settings read from `.dedma.toml`. Every field is optional in the file.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Where the release notes are written.
+    pub output: Option<String>,
+    /// Where the commits are recorded.
+    pub database: String,
+    /// Only the tags matching this glob, such as `v*`, are considered releases.
+    pub tag_pattern: Option<String>,
+    /// Kinds left out of the notes.
+    pub hidden: Vec<String>,
+    /// Notes for players, without the kinds only of interest to the developers.
+    pub players: bool,
+    /// Every kind, in order of appearance in the notes.
+    pub kinds: Vec<KindConfig>,
+    /// What to do with the commits whose kind is not in `kinds`.
+    pub unknown_kinds: UnknownKinds,
+    /// Language of the notes and messages, such as `fr`.
+    pub lang: Option<String>,
+    /// Translation file put over the built-in catalog, such as `locales/{lang}.toml`.
+    pub translations: Option<String>,
+    /// Format of the notes, such as `markdown`.
+    pub format: Option<String>,
+    /// CSS file embedded in the HTML notes, or `default` for the built-in one.
+    pub stylesheet: Option<String>,
+    /// Jinja template writing the notes instead of the format.
+    pub template: Option<String>,
+    /// Web page of the repository, such as `https://github.com/owner/project`, the
+    /// one of the `origin` remote by default.
+    pub repository: Option<String>,
+    /// Forge hosting the repository, guessed from `repository` by default.
+    pub forge: Option<Forge>,
+    /// URL of a commit with `{hash}`, instead of the one of the forge.
+    pub commit_url: Option<String>,
+    /// URL of the changes from `{from}` to `{to}`, instead of the one of the forge.
+    pub compare_url: Option<String>,
+    /// URL of the issue or pull request `{number}`, instead of the one of the forge.
+    pub issue_url: Option<String>,
+    /// URL of the keys such as `ABC-123` with `{key}`, by project of the tracker.
+    pub trackers: BTreeMap<String, String>,
+    /// Settings of the Debian and RPM changelogs.
+    pub package: Package,
+    /// Settings of the Atom and RSS feeds.
+    pub feed: Feed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KindConfig {
+    pub name: String,
+    /// Heading of the kind, the built-in label is used when it is not set.
+    pub label: Option<String>,
+    /// Written before the label, as in `# ✨ New features`.
+    pub emoji: Option<String>,
+    #[serde(default)]
+    pub hidden: bool,
+    /// Place of the kind in the notes, starting at 1.
+    pub position: Option<usize>,
+    /// Keep a Changelog category of the kind, such as `Added`.
+    pub category: Option<Category>,
+    /// Only of interest to the developers, so left out of the notes for players.
+    /// Set for `chore`, `refactor`, `docs`, `style`, `test`, `ci` and `build`.
+    pub developer: Option<bool>,
+}
+
+impl KindConfig {
+    fn new(name: &str) -> KindConfig {
+        KindConfig {
+            name: name.to_string(),
+            label: None,
+            emoji: None,
+            hidden: false,
+            position: None,
+            category: Category::of(name),
+            developer: matches!(
+                name,
+                "chore" | "refactor" | "docs" | "style" | "test" | "ci" | "build"
+            )
+            .then_some(true),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownKinds {
+    /// Written in the section of the `other` kind.
+    #[default]
+    Other,
+    /// Written in their own section, titled with the kind itself.
+    Raw,
+    /// Stop with an error listing the commits.
+    Fail,
+}
+
+impl UnknownKinds {
+    pub fn parse(policy: &str) -> Option<UnknownKinds> {
+        match policy {
+            "other" => Some(UnknownKinds::Other),
+            "raw" => Some(UnknownKinds::Raw),
+            "fail" => Some(UnknownKinds::Fail),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        let kinds = [
+            "other", "feat", "fix", "update", "chore", "refactor", "docs", "style", "test", "perf",
+            "ci", "build", "revert",
+        ];
+        ProjectConfig {
+            output: None,
+            database: String::from("./.dedma/dedma_db.db"),
+            tag_pattern: None,
+            hidden: vec![],
+            players: false,
+            kinds: kinds.iter().map(|kind| KindConfig::new(kind)).collect(),
+            unknown_kinds: UnknownKinds::default(),
+            lang: None,
+            translations: None,
+            format: None,
+            stylesheet: None,
+            template: None,
+            repository: None,
+            forge: None,
+            commit_url: None,
+            compare_url: None,
+            issue_url: None,
+            trackers: BTreeMap::new(),
+            package: Package::default(),
+            feed: Feed::default(),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Reads `path`, or `.dedma.toml` at the root of the repository when no path is given.
+    /// Without a file the built-in settings are used.
+    pub fn load(path: Option<&str>) -> anyhow::Result<ProjectConfig> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let path = repository_root().join(CONFIG_FILE);
+                if !path.exists() {
+                    return Ok(ProjectConfig::default());
+                }
+                path
+            }
+        };
+        let contents = fs::read_to_string(&path)
+            .map_err(|error| anyhow::anyhow!("Could not read '{}': {error}", path.display()))?;
+        let mut config = ProjectConfig::parse(&contents)
+            .map_err(|error| anyhow::anyhow!("Invalid '{}': {error}", path.display()))?;
+
+        // Paths in the file are relative to the file itself
+        let base = path.parent().unwrap_or(Path::new("."));
+        if let Some(output) = &config.output {
+            config.output = Some(base.join(output).to_string_lossy().to_string());
+        }
+        if let Some(translations) = &config.translations {
+            config.translations = Some(base.join(translations).to_string_lossy().to_string());
+        }
+        if let Some(stylesheet) = config.stylesheet.as_ref().filter(|file| *file != "default") {
+            config.stylesheet = Some(base.join(stylesheet).to_string_lossy().to_string());
+        }
+        if let Some(template) = &config.template {
+            config.template = Some(base.join(template).to_string_lossy().to_string());
+        }
+        config.database = base.join(&config.database).to_string_lossy().to_string();
+        Ok(config)
+    }
+
+    pub fn parse(contents: &str) -> Result<ProjectConfig, toml::de::Error> {
+        let mut config: ProjectConfig = toml::from_str(contents)?;
+        config.kinds = merge_kinds(ProjectConfig::default().kinds, config.kinds);
+        Ok(config)
+    }
+
+    /// The kind named `name`, if it is known.
+    pub fn kind(&self, name: &str) -> Option<&KindConfig> {
+        self.kinds.iter().find(|kind| kind.name == name)
+    }
+
+    /// The projects of `trackers`, whose keys such as `ABC-123` are references.
+    pub fn tracker_projects(&self) -> Vec<&str> {
+        self.trackers.keys().map(String::as_str).collect()
+    }
+
+    pub fn is_hidden(&self, name: &str) -> bool {
+        self.hidden.iter().any(|hidden| hidden == name)
+            || self
+                .kind(name)
+                .is_some_and(|kind| kind.hidden || (self.players && kind.developer == Some(true)))
+    }
+
+    /// The links to the commits, compare pages and issues of the forge, and to the keys
+    /// of the other trackers.
+    pub fn links(&self) -> Links {
+        Links::new(
+            self.repository.as_deref(),
+            self.forge,
+            self.commit_url.as_deref(),
+            self.compare_url.as_deref(),
+            self.issue_url.as_deref(),
+        )
+        .with_trackers(self.trackers.clone())
+    }
+
+    /// The `[package]` settings, with the name of the repository and the identity
+    /// of the git user when they are not set.
+    pub fn package(&self) -> Package {
+        let mut package = self.package.clone();
+        if package.name.is_none() {
+            package.name = repository_root()
+                .canonicalize()
+                .ok()
+                .and_then(|root| Some(root.file_name()?.to_string_lossy().to_lowercase()));
+        }
+        if package.maintainer.is_none() {
+            if let (Some(name), Some(email)) = (git_config("user.name"), git_config("user.email")) {
+                package.maintainer = Some(format!("{name} <{email}>"));
+            }
+        }
+        package
+    }
+
+    /// The `[feed]` settings, with the package and the repository when they are not set.
+    pub fn feed(&self, package: &Package) -> Feed {
+        let mut feed = self.feed.clone();
+        if feed.title.is_none() {
+            feed.title = package.name.as_ref().map(|name| format!("{name} releases"));
+        }
+        if feed.link.is_none() {
+            feed.link = self.repository.clone();
+        }
+        if feed.author.is_none() {
+            feed.author = package.name.clone();
+        }
+        feed
+    }
+
+    /// The kinds to write, in order.
+    pub fn visible_kinds(&self) -> Vec<&KindConfig> {
+        self.kinds
+            .iter()
+            .filter(|kind| !self.is_hidden(&kind.name))
+            .collect()
+    }
+}
+
+/// Adds the kinds of the file to the built-in ones, replacing the settings of those
+/// already known, then moves the kinds with a `position` to their place.
+fn merge_kinds(builtin: Vec<KindConfig>, kinds: Vec<KindConfig>) -> Vec<KindConfig> {
+    let mut merged = builtin;
+    for kind in kinds {
+        match merged.iter_mut().find(|known| known.name == kind.name) {
+            Some(known) => {
+                if kind.label.is_some() {
+                    known.label = kind.label;
+                }
+                if kind.emoji.is_some() {
+                    known.emoji = kind.emoji;
+                }
+                if kind.category.is_some() {
+                    known.category = kind.category;
+                }
+                if kind.developer.is_some() {
+                    known.developer = kind.developer;
+                }
+                known.hidden = kind.hidden;
+                known.position = kind.position;
+            }
+            None => merged.push(kind),
+        }
+    }
+
+    let (mut placed, mut result): (Vec<KindConfig>, Vec<KindConfig>) =
+        merged.into_iter().partition(|kind| kind.position.is_some());
+    placed.sort_by_key(|kind| kind.position);
+    for kind in placed {
+        let index = kind
+            .position
+            .unwrap_or(1)
+            .saturating_sub(1)
+            .min(result.len());
+        result.insert(index, kind);
+    }
+    result
+}
+
+/// A setting of git, such as `user.name`.
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").arg("config").arg(key).output().ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !value.is_empty()).then_some(value)
+}
+
+/// The top-level directory of the git repository, or the current directory outside of one.
+fn repository_root() -> PathBuf {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--show-toplevel")
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+        }
+        _ => PathBuf::from("."),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_defaults() {
+        let config = ProjectConfig::parse("output = \"notes.md\"\nhidden = [\"ci\"]").unwrap();
+        assert_eq!(config.output.as_deref(), Some("notes.md"));
+        assert_eq!(config.database, "./.dedma/dedma_db.db");
+        assert_eq!(config.kinds.len(), 13);
+        assert!(!config.visible_kinds().iter().any(|kind| kind.name == "ci"));
+    }
+
+    #[test]
+    fn players_leave_out_developer_kinds() {
+        let config = ProjectConfig::parse(
+            "players = true\n\n[[kinds]]\nname = \"docs\"\ndeveloper = false\n\n[[kinds]]\nname = \"perf\"\ndeveloper = true",
+        )
+        .unwrap();
+        let kinds: Vec<&str> = config
+            .visible_kinds()
+            .iter()
+            .map(|kind| kind.name.as_str())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec!["other", "feat", "fix", "update", "docs", "revert"]
+        );
+    }
+
+    #[test]
+    fn kinds_are_merged_with_the_defaults() {
+        let config = ProjectConfig::parse(
+            "
+hidden = [\"update\", \"refactor\", \"docs\", \"style\", \"test\", \"perf\", \"ci\", \"build\", \"revert\"]
+unknown_kinds = \"raw\"
+
+[[kinds]]
+name = \"security\"
+label = \"Security\"
+emoji = \"🔒\"
+position = 1
+category = \"Security\"
+
+[[kinds]]
+name = \"feat\"
+label = \"What's new\"
+
+[[kinds]]
+name = \"chore\"
+hidden = true
+
+[[kinds]]
+name = \"deps\"
+",
+        )
+        .unwrap();
+        let kinds: Vec<&str> = config
+            .visible_kinds()
+            .iter()
+            .map(|kind| kind.name.as_str())
+            .collect();
+        assert_eq!(kinds, vec!["security", "other", "feat", "fix", "deps"]);
+        assert_eq!(
+            config.kind("feat").unwrap().label.as_deref(),
+            Some("What's new")
+        );
+        assert_eq!(
+            config.kind("security").unwrap().emoji.as_deref(),
+            Some("🔒")
+        );
+        assert_eq!(
+            config.kind("security").unwrap().category,
+            Some(Category::Security)
+        );
+        assert_eq!(config.kind("feat").unwrap().category, Some(Category::Added));
+        assert_eq!(config.unknown_kinds, UnknownKinds::Raw);
+    }
+
+    #[test]
+    fn unknown_keys_are_errors() {
+        assert!(ProjectConfig::parse("ouptut = \"notes.md\"").is_err());
+    }
+}
@@ -0,0 +1,373 @@
+//! Release notes are gathered into a [`Release`], then written by a [`Renderer`].
+//! `--format` picks the renderer among the [`Formats`], which other tools can extend
+//! with their own before calling [`crate::run_with`].
+
+mod appstream;
+mod chat;
+mod feed;
+mod html;
+mod json;
+mod keep_a_changelog;
+mod markdown;
+mod package;
+mod store;
+mod template;
+mod time;
+
+use crate::locale::Catalog;
+pub use appstream::AppStream;
+pub use chat::{Discord, Slack, Teams};
+pub use feed::{Atom, Feed, Rss};
+pub use html::{Html, STYLESHEET};
+pub use json::{Json, JSON_VERSION};
+pub use keep_a_changelog::KeepAChangelog;
+pub use markdown::Markdown;
+pub use package::{Debian, Package, Rpm};
+use serde::{Deserialize, Serialize};
+pub use store::{Itch, Steam};
+pub use template::Template;
+
+/// One release, as given to the renderers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Release {
+    /// Tag the commits are recorded under, such as `v0.2.0` or `Unreleased`.
+    pub tag: String,
+    /// The release before this one, unknown for the commits read from a file.
+    pub previous_tag: Option<String>,
+    /// Date of the tagged commit as `YYYY-MM-DD`, unknown for unreleased commits.
+    pub date: Option<String>,
+    /// Unix time of the tagged commit, unknown like `date`.
+    pub time: Option<i64>,
+    /// Language of the labels and of the entries, such as `fr`.
+    pub language: String,
+    /// Label of the breaking changes.
+    pub breaking_label: String,
+    /// Text replacing the entries left out of a message too long, such as
+    /// `and {count} more`.
+    pub more_label: String,
+    /// The breaking changes, which are also in the section of their kind.
+    pub breaking: Vec<Entry>,
+    /// The sections, in the order of the configuration.
+    pub sections: Vec<Section>,
+    /// Revisions compared by `compare_url`, such as `v0.1.0...v0.2.0` or `v0.1.0...HEAD`.
+    pub compare_range: Option<String>,
+    /// Page comparing this release with the previous one, when the repository is known.
+    pub compare_url: Option<String>,
+}
+
+/// The commits of a kind. The section of `other` may gather several kinds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Section {
+    pub kind: String,
+    pub label: String,
+    /// Where the kind goes in a Keep a Changelog document, nowhere when it is `None`.
+    pub category: Option<Category>,
+    pub scopes: Vec<Scope>,
+}
+
+/// The categories of Keep a Changelog, in the order of its documents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum Category {
+    Added,
+    Changed,
+    Deprecated,
+    Removed,
+    Fixed,
+    Security,
+}
+
+impl Category {
+    /// The category of the built-in kinds, the others have none.
+    pub fn of(kind: &str) -> Option<Category> {
+        match kind {
+            "feat" => Some(Category::Added),
+            "fix" => Some(Category::Fixed),
+            "other" | "update" | "refactor" | "perf" | "revert" => Some(Category::Changed),
+            _ => None,
+        }
+    }
+}
+
+/// The commits of a scope, such as `ui` in `feat(ui): ...`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scope {
+    /// The scope as written in the commits, `other` for the commits without one.
+    pub name: String,
+    /// Heading of the scope, `None` for the commits without one.
+    pub label: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+/// One commit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entry {
+    /// The description of the commit, or its `Changelog-<language>` trailer.
+    pub content: String,
+    pub hash: String,
+    /// The author then the `Co-authored-by` trailers, empty for the commits read from a file.
+    pub authors: Vec<String>,
+    /// Marked with `!` or a `BREAKING CHANGE` trailer.
+    pub breaking: bool,
+    /// Text of the `BREAKING CHANGE` trailer, empty when there is none.
+    pub breaking_note: String,
+    /// The values of the `Refs` trailers, as written.
+    pub refs: Vec<String>,
+    /// The issues and pull requests the commit refers to.
+    pub references: Vec<Reference>,
+    /// Page of the commit on the forge, when the repository is known.
+    pub url: Option<String>,
+}
+
+/// An issue or pull request a commit refers to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Reference {
+    /// `#133`, `owner/project#133`, or the key of another tracker such as `ABC-123`.
+    pub key: String,
+    /// Closed by the commit, as in `Closes #133`.
+    pub closes: bool,
+    /// Page of the issue, when its tracker is known.
+    pub url: Option<String>,
+}
+
+impl Entry {
+    /// The first 7 characters of the hash, as git abbreviates it.
+    pub fn short_hash(&self) -> &str {
+        self.hash.get(..7).unwrap_or(&self.hash)
+    }
+}
+
+impl Release {
+    /// The message `key` of the built-in catalog of the language of the release, with the
+    /// tag in place of `{tag}`, for the errors of the renderers.
+    pub(crate) fn message(&self, key: &str) -> String {
+        Catalog::new(&self.language).format(key, &[("tag", &self.tag)])
+    }
+
+    /// The tag, followed by the date when it is known: `v0.2.0 (2024-05-01)`.
+    pub fn heading(&self) -> String {
+        match &self.date {
+            Some(date) => format!("{} ({date})", self.tag),
+            None => self.tag.clone(),
+        }
+    }
+
+    /// The `more_label` for `count` entries left out, in English when it is not set.
+    pub fn more(&self, count: usize) -> String {
+        let label = match self.more_label.as_str() {
+            "" => "and {count} more",
+            label => label,
+        };
+        label.replace("{count}", &count.to_string())
+    }
+
+    /// The tag without the `v` of `v1.0.0`.
+    pub fn version(&self) -> &str {
+        match self.tag.strip_prefix('v') {
+            Some(version) if version.starts_with(|c: char| c.is_ascii_digit()) => version,
+            _ => &self.tag,
+        }
+    }
+}
+
+/// Writes releases in one format.
+pub trait Renderer {
+    /// Extension of the files written in this format, such as `md`.
+    fn extension(&self) -> &str;
+
+    /// The notes of a single release.
+    fn render(&self, release: &Release) -> anyhow::Result<String>;
+
+    /// The notes of a release under its own heading, as in a changelog.
+    fn render_section(&self, release: &Release) -> anyhow::Result<String> {
+        self.render(release)
+    }
+
+    /// Every release in one document, newest first.
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let sections = releases
+            .iter()
+            .map(|release| self.render_section(release))
+            .collect::<anyhow::Result<Vec<String>>>()?;
+        Ok(sections.join("\n"))
+    }
+
+    /// Whether `dedma changelog` writes every release recorded in the database rather than
+    /// only the git tags.
+    fn recorded_releases(&self) -> bool {
+        false
+    }
+
+    /// Adds `release` to a `document` written by `render_changelog`, for `--update`.
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let _ = document;
+        anyhow::bail!(release.message("messages.update_unsupported"))
+    }
+
+    /// `document` without the notes of the tag of `release`, unchanged when they are
+    /// not in it. `--update` replaces the notes of the unreleased commits with it, and
+    /// removes them once the release is tagged.
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let _ = release;
+        Ok(document.to_string())
+    }
+}
+
+/// The renderers `--format` chooses from, by name.
+pub struct Formats {
+    renderers: Vec<(String, Box<dyn Renderer>)>,
+    /// The names given to `register`, whose formats the configuration doesn't replace.
+    registered: Vec<String>,
+}
+
+impl Default for Formats {
+    /// The formats shipped with dedma.
+    fn default() -> Self {
+        let mut formats = Formats {
+            renderers: vec![],
+            registered: vec![],
+        };
+        formats.insert("markdown", Markdown);
+        formats.insert("json", Json);
+        formats.insert("html", Html::default());
+        formats.insert("keepachangelog", KeepAChangelog);
+        formats.insert(
+            "debian",
+            Debian {
+                package: Package::default(),
+            },
+        );
+        formats.insert(
+            "rpm",
+            Rpm {
+                package: Package::default(),
+            },
+        );
+        formats.insert("appstream", AppStream);
+        formats.insert(
+            "atom",
+            Atom {
+                feed: Feed::default(),
+            },
+        );
+        formats.insert(
+            "rss",
+            Rss {
+                feed: Feed::default(),
+            },
+        );
+        formats.insert("slack", Slack);
+        formats.insert("discord", Discord);
+        formats.insert("teams", Teams);
+        formats.insert("steam", Steam);
+        formats.insert("itch", Itch);
+        formats
+    }
+}
+
+impl Formats {
+    /// Adds a format, replacing the one with the same name.
+    pub fn register(&mut self, name: &str, renderer: impl Renderer + 'static) -> &mut Formats {
+        self.registered.push(name.to_string());
+        self.insert(name, renderer)
+    }
+
+    /// Replaces a built-in format by one set up by the configuration, unless `register`
+    /// was given another one under this name.
+    pub(crate) fn configure(&mut self, name: &str, renderer: impl Renderer + 'static) {
+        if !self.registered.iter().any(|registered| registered == name) {
+            self.insert(name, renderer);
+        }
+    }
+
+    fn insert(&mut self, name: &str, renderer: impl Renderer + 'static) -> &mut Formats {
+        self.renderers.retain(|(known, _)| known != name);
+        self.renderers.push((name.to_string(), Box::new(renderer)));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Renderer> {
+        self.renderers
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, renderer)| renderer.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.renderers
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Tags;
+
+    impl Renderer for Tags {
+        fn extension(&self) -> &str {
+            "txt"
+        }
+
+        fn render(&self, release: &Release) -> anyhow::Result<String> {
+            Ok(format!("{}\n", release.tag))
+        }
+    }
+
+    #[test]
+    fn formats_can_be_added() {
+        let mut formats = Formats::default();
+        formats.register("tags", Tags);
+        assert_eq!(
+            formats.names(),
+            vec![
+                "markdown",
+                "json",
+                "html",
+                "keepachangelog",
+                "debian",
+                "rpm",
+                "appstream",
+                "atom",
+                "rss",
+                "slack",
+                "discord",
+                "teams",
+                "steam",
+                "itch",
+                "tags"
+            ]
+        );
+
+        let releases = [
+            Release {
+                tag: String::from("v0.2.0"),
+                ..Default::default()
+            },
+            Release {
+                tag: String::from("v0.1.0"),
+                ..Default::default()
+            },
+        ];
+        let tags = formats.get("tags").unwrap();
+        assert_eq!(
+            tags.render_changelog(&releases).unwrap(),
+            "v0.2.0\n\nv0.1.0\n"
+        );
+        assert!(tags.insert("", &releases[0]).is_err());
+        assert!(formats.get("pdf").is_none());
+
+        formats.register("rss", Tags);
+        formats.configure(
+            "rss",
+            Rss {
+                feed: Feed::default(),
+            },
+        );
+        formats.configure("atom", Tags);
+        assert_eq!(formats.get("rss").unwrap().extension(), "txt");
+        assert_eq!(formats.get("atom").unwrap().extension(), "txt");
+    }
+}
@@ -0,0 +1,269 @@
+use super::{html::escape, Entry, Release, Renderer};
+use std::cmp::Ordering;
+
+/// The `<release>` elements of an AppStream metainfo file.
+pub struct AppStream;
+
+/// An `<li>` of the description, after the label of its scope.
+fn item(scope: Option<&String>, entry: &Entry) -> String {
+    let text = match scope {
+        Some(scope) => format!("{scope}: {}", entry.content),
+        None => entry.content.clone(),
+    };
+    format!("      <li>{}</li>", escape(&text))
+}
+
+/// The `<release>` element, each line starting with `indent`. The unreleased commits
+/// make a `development` release without a date.
+fn element(release: &Release, indent: &str) -> String {
+    let mut attributes = format!("version=\"{}\"", escape(release.version()));
+    match &release.date {
+        Some(date) => attributes.push_str(&format!(" date=\"{}\"", escape(date))),
+        None => attributes.push_str(" type=\"development\""),
+    }
+    let mut lines = vec![
+        format!("<release {attributes}>"),
+        String::from("  <description>"),
+    ];
+    if !release.breaking.is_empty() {
+        lines.push(format!("    <p>{}</p>", escape(&release.breaking_label)));
+        lines.push(String::from("    <ul>"));
+        lines.extend(release.breaking.iter().map(|entry| item(None, entry)));
+        lines.push(String::from("    </ul>"));
+    }
+    for section in &release.sections {
+        lines.push(format!("    <p>{}</p>", escape(&section.label)));
+        lines.push(String::from("    <ul>"));
+        for scope in &section.scopes {
+            lines.extend(
+                scope
+                    .entries
+                    .iter()
+                    .map(|entry| item(scope.label.as_ref(), entry)),
+            );
+        }
+        lines.push(String::from("    </ul>"));
+    }
+    lines.push(String::from("  </description>"));
+    lines.push(String::from("</release>"));
+    lines
+        .iter()
+        .map(|line| format!("{indent}{line}\n"))
+        .collect()
+}
+
+/// Compares versions part by part, the numbers as numbers: `1.10` comes after `1.9`.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<String> {
+        version
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for (a, b) in a.iter().zip(&b) {
+        let order = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// The value of `version="..."` in a `<release>` tag.
+fn release_version(line: &str) -> Option<&str> {
+    let start = line.find("version=\"")? + "version=\"".len();
+    let end = line[start..].find('"')?;
+    Some(&line[start..start + end])
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+impl Renderer for AppStream {
+    fn extension(&self) -> &str {
+        "xml"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(element(release, ""))
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let mut document = String::from("<releases>\n");
+        for release in releases {
+            document.push_str(&element(release, "  "));
+        }
+        document.push_str("</releases>\n");
+        Ok(document)
+    }
+
+    /// Puts the release among the `<releases>` of a metainfo file, newest first,
+    /// creating the `<releases>` element at the end of the component if needed.
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return self.render_changelog(std::slice::from_ref(release));
+        }
+        let lines: Vec<&str> = document.lines().collect();
+        let releases: Vec<(usize, &str)> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with("<release "))
+            .filter_map(|(index, line)| Some((index, release_version(line)?)))
+            .collect();
+        if releases
+            .iter()
+            .any(|(_, version)| *version == release.version())
+        {
+            anyhow::bail!(release.message("messages.already_in_changelog"));
+        }
+
+        let older = releases
+            .iter()
+            .find(|(_, version)| compare_versions(version, release.version()) == Ordering::Less);
+        let (position, element) = match older {
+            Some((index, _)) => (*index, element(release, indentation(lines[*index]))),
+            None => {
+                let Some(end) = lines.iter().position(|line| line.trim() == "</releases>") else {
+                    let Some(component) =
+                        lines.iter().rposition(|line| line.trim() == "</component>")
+                    else {
+                        anyhow::bail!("The metainfo file has no <releases> nor </component>");
+                    };
+                    let indent = format!("{}  ", indentation(lines[component]));
+                    let element = format!(
+                        "{indent}<releases>\n{}{indent}</releases>\n",
+                        element(release, &format!("{indent}  "))
+                    );
+                    return Ok(insert_lines(&lines, component, &element));
+                };
+                let indent = match releases.last() {
+                    Some((index, _)) => indentation(lines[*index]).to_string(),
+                    None => format!("{}  ", indentation(lines[end])),
+                };
+                (end, element(release, &indent))
+            }
+        };
+        Ok(insert_lines(&lines, position, &element))
+    }
+
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let lines: Vec<&str> = document.lines().collect();
+        let Some(start) = lines.iter().position(|line| {
+            line.trim_start().starts_with("<release ")
+                && release_version(line) == Some(release.version())
+        }) else {
+            return Ok(document.to_string());
+        };
+        let end = if lines[start].trim_end().ends_with("/>") {
+            start
+        } else {
+            lines[start..]
+                .iter()
+                .position(|line| line.trim() == "</release>")
+                .map_or(start, |end| start + end)
+        };
+        let mut result = String::new();
+        for line in lines[..start].iter().chain(&lines[end + 1..]) {
+            result.push_str(line);
+            result.push('\n');
+        }
+        Ok(result)
+    }
+}
+
+/// The `lines` with `text` inserted before the line at `position`.
+fn insert_lines(lines: &[&str], position: usize, text: &str) -> String {
+    let mut result = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if index == position {
+            result.push_str(text);
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Scope, Section};
+
+    fn release(tag: &str, date: Option<&str>) -> Release {
+        Release {
+            tag: tag.to_string(),
+            date: date.map(str::to_string),
+            sections: vec![Section {
+                kind: String::from("fix"),
+                label: String::from("Bug fix"),
+                scopes: vec![Scope {
+                    name: String::from("ui"),
+                    label: Some(String::from("UI")),
+                    entries: vec![Entry {
+                        content: String::from("crash with <empty> names"),
+                        ..Default::default()
+                    }],
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn appstream_work() {
+        let element = "\
+<release version=\"1.10.0\" date=\"2024-05-02\">
+  <description>
+    <p>Bug fix</p>
+    <ul>
+      <li>UI: crash with &lt;empty&gt; names</li>
+    </ul>
+  </description>
+</release>
+";
+        let v1_10 = release("v1.10.0", Some("2024-05-02"));
+        assert_eq!(AppStream.render(&v1_10).unwrap(), element);
+
+        let metainfo = "\
+<component type=\"desktop-application\">
+  <id>com.example.Towers</id>
+  <releases>
+    <release version=\"1.11.0\" date=\"2024-06-01\"/>
+    <release version=\"1.9.0\" date=\"2024-03-12\"/>
+  </releases>
+</component>
+";
+        let result = AppStream.insert(metainfo, &v1_10).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        assert_eq!(
+            lines[3],
+            "    <release version=\"1.11.0\" date=\"2024-06-01\"/>"
+        );
+        assert_eq!(
+            lines[4],
+            "    <release version=\"1.10.0\" date=\"2024-05-02\">"
+        );
+        assert_eq!(lines[9], "        </ul>");
+        assert_eq!(
+            lines[12],
+            "    <release version=\"1.9.0\" date=\"2024-03-12\"/>"
+        );
+        assert!(AppStream.insert(&result, &v1_10).is_err());
+        assert_eq!(AppStream.remove(&result, &v1_10).unwrap(), metainfo);
+
+        let metainfo = "<component>\n  <id>com.example.Towers</id>\n</component>\n";
+        let result = AppStream
+            .insert(metainfo, &release("Unreleased", None))
+            .unwrap();
+        assert!(result
+            .contains("  <releases>\n    <release version=\"Unreleased\" type=\"development\">\n"));
+        assert!(result.ends_with("  </releases>\n</component>\n"));
+    }
+}
@@ -0,0 +1,297 @@
+use super::{Entry, Release, Renderer};
+use serde_json::{json, Value};
+
+/// Most characters of a Slack header.
+const SLACK_HEADER: usize = 150;
+/// Most characters of a Slack section.
+const SLACK_SECTION: usize = 3000;
+/// Most blocks of a Slack message.
+const SLACK_BLOCKS: usize = 50;
+/// Most characters of the title and of the field names of a Discord embed.
+const DISCORD_TITLE: usize = 256;
+/// Most characters of a Discord field value.
+const DISCORD_FIELD: usize = 1024;
+/// Most fields of a Discord embed.
+const DISCORD_FIELDS: usize = 25;
+/// Most characters of a whole Discord embed.
+const DISCORD_EMBED: usize = 6000;
+/// Most bytes of text in a Teams card, leaving room for the card itself in the
+/// 28 KB of a Teams message.
+const TEAMS_TEXT: usize = 24000;
+
+/// The notes as a Slack message made of Block Kit blocks.
+pub struct Slack;
+
+/// The notes as a Discord webhook message with one embed.
+pub struct Discord;
+
+/// The notes as a Microsoft Teams message holding an adaptive card.
+pub struct Teams;
+
+/// The label and the lines of each kind, the breaking changes first.
+fn kinds(release: &Release, text: impl Fn(&str) -> String) -> Vec<(String, Vec<String>)> {
+    let line = |scope: Option<&String>, entry: &Entry| match scope {
+        Some(scope) => format!("{}: {}", text(scope), text(&entry.content)),
+        None => text(&entry.content),
+    };
+    let mut kinds = vec![];
+    if !release.breaking.is_empty() {
+        let lines = release.breaking.iter().map(|entry| line(None, entry));
+        kinds.push((text(&release.breaking_label), lines.collect()));
+    }
+    for section in &release.sections {
+        let mut lines = vec![];
+        for scope in &section.scopes {
+            lines.extend(
+                scope
+                    .entries
+                    .iter()
+                    .map(|entry| line(scope.label.as_ref(), entry)),
+            );
+        }
+        kinds.push((text(&section.label), lines));
+    }
+    kinds
+}
+
+/// `lines` under `bullet`, one per line, in at most `limit` bytes. The lines that
+/// don't fit are replaced by the `more` text of the release.
+fn bullets(release: &Release, bullet: &str, lines: &[String], limit: usize) -> String {
+    let room = limit.saturating_sub(release.more(lines.len()).len() + 1);
+    let mut text = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let line = format!("{bullet}{line}");
+        let last = index + 1 == lines.len();
+        let length = text.len() + usize::from(!text.is_empty()) + line.len();
+        if length > room && !(last && length <= limit) {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&release.more(lines.len() - index));
+            return text;
+        }
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&line);
+    }
+    text
+}
+
+/// At most `limit` characters of `text`, ending with `…` when it is cut.
+fn shorten(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut text: String = text.chars().take(limit - 1).collect();
+    text.push('…');
+    text
+}
+
+/// Escapes the characters with a meaning in Slack messages.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn payload(value: Value) -> anyhow::Result<String> {
+    Ok(format!("{}\n", serde_json::to_string_pretty(&value)?))
+}
+
+fn single_release() -> anyhow::Result<String> {
+    anyhow::bail!("A chat message holds the notes of a single release")
+}
+
+impl Renderer for Slack {
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let heading = release.heading();
+        let mut blocks = vec![json!({
+            "type": "header",
+            "text": {"type": "plain_text", "text": shorten(&heading, SLACK_HEADER)}
+        })];
+        let kinds = kinds(release, slack_escape);
+        // The last block is kept for the kinds left out
+        let shown = if kinds.len() < SLACK_BLOCKS {
+            kinds.len()
+        } else {
+            SLACK_BLOCKS - 2
+        };
+        for (label, lines) in &kinds[..shown] {
+            let title = format!("*{label}*\n");
+            let text = bullets(release, "• ", lines, SLACK_SECTION - title.len());
+            blocks.push(json!({
+                "type": "section",
+                "text": {"type": "mrkdwn", "text": format!("{title}{text}")}
+            }));
+        }
+        let left: usize = kinds[shown..].iter().map(|(_, lines)| lines.len()).sum();
+        if left > 0 {
+            blocks.push(json!({
+                "type": "context",
+                "elements": [{"type": "mrkdwn", "text": release.more(left)}]
+            }));
+        }
+        payload(json!({"text": heading, "blocks": blocks}))
+    }
+
+    fn render_changelog(&self, _: &[Release]) -> anyhow::Result<String> {
+        single_release()
+    }
+}
+
+impl Renderer for Discord {
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let title = shorten(&release.heading(), DISCORD_TITLE);
+        // The footer takes what is left of the room for the kinds left out
+        let mut room = DISCORD_EMBED - title.chars().count() - 64;
+        let mut fields = vec![];
+        let mut left = 0;
+        for (label, lines) in kinds(release, str::to_string) {
+            let name = shorten(&label, DISCORD_TITLE);
+            let length = name.chars().count();
+            if fields.len() == DISCORD_FIELDS || room < length + 64 {
+                left += lines.len();
+                continue;
+            }
+            let value = bullets(release, "- ", &lines, DISCORD_FIELD.min(room - length));
+            room -= length + value.chars().count();
+            fields.push(json!({"name": name, "value": value}));
+        }
+        let mut embed = json!({"title": title, "fields": fields});
+        if let Some(url) = &release.compare_url {
+            embed["url"] = json!(url);
+        }
+        if left > 0 {
+            embed["footer"] = json!({"text": release.more(left)});
+        }
+        payload(json!({"embeds": [embed]}))
+    }
+
+    fn render_changelog(&self, _: &[Release]) -> anyhow::Result<String> {
+        single_release()
+    }
+}
+
+impl Renderer for Teams {
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let heading = release.heading();
+        let mut room = TEAMS_TEXT - heading.len();
+        let mut body = vec![json!({
+            "type": "TextBlock",
+            "text": heading,
+            "size": "Large",
+            "weight": "Bolder",
+            "wrap": true
+        })];
+        for (label, lines) in kinds(release, str::to_string) {
+            let text = bullets(release, "- ", &lines, room.saturating_sub(label.len()));
+            room = room.saturating_sub(label.len() + text.len());
+            body.push(
+                json!({"type": "TextBlock", "text": label, "weight": "Bolder", "wrap": true}),
+            );
+            body.push(json!({"type": "TextBlock", "text": text, "wrap": true}));
+        }
+        let card = json!({
+            "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+            "type": "AdaptiveCard",
+            "version": "1.4",
+            "body": body
+        });
+        payload(json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": card
+            }]
+        }))
+    }
+
+    fn render_changelog(&self, _: &[Release]) -> anyhow::Result<String> {
+        single_release()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Scope, Section};
+
+    fn release(entries: usize) -> Release {
+        Release {
+            tag: String::from("v0.2.0"),
+            date: Some(String::from("2024-05-01")),
+            compare_url: Some(String::from("https://example.com/compare/v0.1.0...v0.2.0")),
+            sections: vec![Section {
+                kind: String::from("fix"),
+                label: String::from("Bug fix"),
+                scopes: vec![Scope {
+                    name: String::from("ui"),
+                    label: Some(String::from("UI")),
+                    entries: (0..entries)
+                        .map(|index| Entry {
+                            content: format!("crash <{index}> when the menu opens"),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn parse(payload: String) -> Value {
+        serde_json::from_str(&payload).unwrap()
+    }
+
+    #[test]
+    fn slack_works() {
+        let message = parse(Slack.render(&release(2)).unwrap());
+        assert_eq!(message["text"], "v0.2.0 (2024-05-01)");
+        assert_eq!(message["blocks"][0]["type"], "header");
+        assert_eq!(
+            message["blocks"][1]["text"]["text"],
+            "*Bug fix*\n• UI: crash &lt;0&gt; when the menu opens\n• UI: crash &lt;1&gt; when the menu opens"
+        );
+
+        let message = parse(Slack.render(&release(200)).unwrap());
+        let text = message["blocks"][1]["text"]["text"].as_str().unwrap();
+        assert!(text.len() <= SLACK_SECTION);
+        assert!(text.ends_with("\nand 134 more"));
+        assert!(Slack.render_changelog(&[release(1)]).is_err());
+    }
+
+    #[test]
+    fn discord_and_teams_work() {
+        let message = parse(Discord.render(&release(100)).unwrap());
+        let embed = &message["embeds"][0];
+        assert_eq!(embed["url"], "https://example.com/compare/v0.1.0...v0.2.0");
+        assert_eq!(embed["fields"][0]["name"], "Bug fix");
+        let value = embed["fields"][0]["value"].as_str().unwrap();
+        assert!(value.len() <= DISCORD_FIELD);
+        assert!(value.starts_with("- UI: crash <0> when the menu opens\n"));
+        assert!(value.ends_with("\nand 73 more"));
+
+        let message = parse(Teams.render(&release(1)).unwrap());
+        let card = &message["attachments"][0]["content"];
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(card["body"][1]["text"], "Bug fix");
+        assert_eq!(
+            card["body"][2]["text"],
+            "- UI: crash <0> when the menu opens"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use super::html::{anchor, article, escape};
+use super::time::{release_time, rfc2822_date, rfc3339_date};
+use super::{Release, Renderer};
+use serde::Deserialize;
+
+/// The `[feed]` section of `.dedma.toml`, used by the Atom and RSS feeds.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Feed {
+    /// Title of the feed, `<package> releases` by default.
+    pub title: Option<String>,
+    /// Page showing the notes, the `repository` by default.
+    pub link: Option<String>,
+    /// Author of the feed, the name of the package by default.
+    pub author: Option<String>,
+}
+
+impl Feed {
+    fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Releases")
+    }
+
+    /// The `id` of the feed: its link, or a URN when there is none.
+    fn id(&self) -> String {
+        match &self.link {
+            Some(link) => link.clone(),
+            None => format!("urn:dedma:{}", anchor(self.title())),
+        }
+    }
+
+    /// The `id` of a release, which only depends on the feed and the tag.
+    fn entry_id(&self, release: &Release) -> String {
+        match &self.link {
+            Some(link) => format!("{link}#{}", anchor(&release.tag)),
+            None => format!("{}:{}", self.id(), anchor(&release.tag)),
+        }
+    }
+
+    /// The notes of a release as escaped HTML.
+    fn content(&self, release: &Release) -> String {
+        escape(&article(release, 1, &format!("{}-", anchor(&release.tag))))
+    }
+}
+
+/// `document` without the element between `open` and `close` that holds `id`.
+fn remove_element(document: &str, id: &str, open: &str, close: &str) -> String {
+    let Some(position) = document.find(id) else {
+        return document.to_string();
+    };
+    let start = document[..position].rfind(open);
+    let end = document[position..].find(close);
+    let (Some(start), Some(end)) = (start, end) else {
+        return document.to_string();
+    };
+    let end = position + end + close.len();
+    format!("{}{}", &document[..start], &document[end..])
+}
+
+/// The releases as an Atom feed.
+pub struct Atom {
+    pub feed: Feed,
+}
+
+impl Atom {
+    fn entry(&self, release: &Release) -> String {
+        let mut entry = format!(
+            "  <entry>\n    <title>{}</title>\n    <id>{}</id>\n",
+            escape(&release.heading()),
+            escape(&self.feed.entry_id(release))
+        );
+        if let Some(link) = &self.feed.link {
+            entry.push_str(&format!(
+                "    <link href=\"{}#{}\"/>\n",
+                escape(link),
+                anchor(&release.tag)
+            ));
+        }
+        entry.push_str(&format!(
+            "    <updated>{}</updated>\n    <content type=\"html\">{}</content>\n  </entry>\n",
+            rfc3339_date(release_time(release)),
+            self.feed.content(release)
+        ));
+        entry
+    }
+}
+
+impl Renderer for Atom {
+    fn extension(&self) -> &str {
+        "atom"
+    }
+
+    fn recorded_releases(&self) -> bool {
+        true
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        self.render_changelog(std::slice::from_ref(release))
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let updated = releases.iter().map(release_time).max().unwrap_or_default();
+        let mut feed = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n  <title>{}</title>\n  <id>{}</id>\n",
+            escape(self.feed.title()),
+            escape(&self.feed.id())
+        );
+        if let Some(link) = &self.feed.link {
+            feed.push_str(&format!("  <link href=\"{}\"/>\n", escape(link)));
+        }
+        feed.push_str(&format!(
+            "  <updated>{}</updated>\n  <author><name>{}</name></author>\n",
+            rfc3339_date(updated),
+            escape(self.feed.author.as_deref().unwrap_or(self.feed.title()))
+        ));
+        for release in releases {
+            feed.push_str(&self.entry(release));
+        }
+        feed.push_str("</feed>\n");
+        Ok(feed)
+    }
+
+    /// Puts the release above the other entries and updates the date of the feed.
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return self.render(release);
+        }
+        let id = format!("<id>{}</id>", escape(&self.feed.entry_id(release)));
+        if document.contains(&id) {
+            anyhow::bail!(release.message("messages.already_in_feed"));
+        }
+        let Some(position) = document
+            .find("  <entry>")
+            .or_else(|| document.find("</feed>"))
+        else {
+            anyhow::bail!("The feed has no </feed>");
+        };
+        let mut feed = format!(
+            "{}{}{}",
+            &document[..position],
+            self.entry(release),
+            &document[position..]
+        );
+        if let (Some(start), Some(end)) = (feed.find("<updated>"), feed.find("</updated>")) {
+            if start < position {
+                let updated = rfc3339_date(release_time(release));
+                feed.replace_range(start + "<updated>".len()..end, &updated);
+            }
+        }
+        Ok(feed)
+    }
+
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let id = format!("<id>{}</id>", escape(&self.feed.entry_id(release)));
+        Ok(remove_element(document, &id, "  <entry>\n", "  </entry>\n"))
+    }
+}
+
+/// The releases as an RSS 2.0 feed.
+pub struct Rss {
+    pub feed: Feed,
+}
+
+impl Rss {
+    fn item(&self, release: &Release) -> String {
+        let mut item = format!(
+            "    <item>\n      <title>{}</title>\n",
+            escape(&release.heading())
+        );
+        if let Some(link) = &self.feed.link {
+            item.push_str(&format!(
+                "      <link>{}#{}</link>\n",
+                escape(link),
+                anchor(&release.tag)
+            ));
+        }
+        item.push_str(&format!(
+            "      <guid isPermaLink=\"false\">{}</guid>\n      <pubDate>{}</pubDate>\n      <description>{}</description>\n    </item>\n",
+            escape(&self.feed.entry_id(release)),
+            rfc2822_date(release_time(release)),
+            self.feed.content(release)
+        ));
+        item
+    }
+}
+
+impl Renderer for Rss {
+    fn extension(&self) -> &str {
+        "rss"
+    }
+
+    fn recorded_releases(&self) -> bool {
+        true
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        self.render_changelog(std::slice::from_ref(release))
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let Some(link) = &self.feed.link else {
+            anyhow::bail!("An RSS feed needs a link: set `link` in the [feed] section of .dedma.toml, or `repository`");
+        };
+        let mut feed = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\">\n  <channel>\n    <title>{0}</title>\n    <link>{1}</link>\n    <description>{0}</description>\n",
+            escape(self.feed.title()),
+            escape(link)
+        );
+        for release in releases {
+            feed.push_str(&self.item(release));
+        }
+        feed.push_str("  </channel>\n</rss>\n");
+        Ok(feed)
+    }
+
+    /// Puts the release above the other items.
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return self.render(release);
+        }
+        let guid = format!(">{}</guid>", escape(&self.feed.entry_id(release)));
+        if document.contains(&guid) {
+            anyhow::bail!(release.message("messages.already_in_feed"));
+        }
+        let Some(position) = document
+            .find("    <item>")
+            .or_else(|| document.find("  </channel>"))
+        else {
+            anyhow::bail!("The feed has no </channel>");
+        };
+        Ok(format!(
+            "{}{}{}",
+            &document[..position],
+            self.item(release),
+            &document[position..]
+        ))
+    }
+
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let guid = format!(">{}</guid>", escape(&self.feed.entry_id(release)));
+        Ok(remove_element(
+            document,
+            &guid,
+            "    <item>\n",
+            "    </item>\n",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Entry, Scope, Section};
+
+    fn release(tag: &str, time: i64) -> Release {
+        Release {
+            tag: tag.to_string(),
+            time: Some(time),
+            sections: vec![Section {
+                kind: String::from("fix"),
+                label: String::from("Bug fix"),
+                scopes: vec![Scope {
+                    name: String::from("other"),
+                    label: None,
+                    entries: vec![Entry {
+                        content: String::from("crash with <empty> names"),
+                        ..Default::default()
+                    }],
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn feed() -> Feed {
+        Feed {
+            title: Some(String::from("Towers patch notes")),
+            link: Some(String::from("https://example.com/notes")),
+            author: None,
+        }
+    }
+
+    #[test]
+    fn atom_work() {
+        let atom = Atom { feed: feed() };
+        let document = atom.render(&release("v0.1.0", 0)).unwrap();
+        assert!(document.contains("<updated>1970-01-01T00:00:00Z</updated>\n  <author><name>Towers patch notes</name></author>\n"));
+        assert!(document.contains("    <id>https://example.com/notes#v0-1-0</id>\n"));
+        assert!(document.contains(
+            "&lt;li data-commit=&quot;&quot;&gt;crash with &amp;lt;empty&amp;gt; names&lt;/li&gt;"
+        ));
+
+        let document = atom
+            .insert(&document, &release("v0.2.0", 1714653296))
+            .unwrap();
+        assert!(document.contains("  <updated>2024-05-02T12:34:56Z</updated>\n"));
+        let newest = document.find("notes#v0-2-0</id>").unwrap();
+        assert!(newest < document.find("notes#v0-1-0</id>").unwrap());
+        assert!(atom
+            .insert(&document, &release("v0.2.0", 1714653296))
+            .is_err());
+        let removed = atom
+            .remove(&document, &release("v0.2.0", 1714653296))
+            .unwrap();
+        assert!(!removed.contains("notes#v0-2-0"));
+        assert!(removed.contains("    <id>https://example.com/notes#v0-1-0</id>\n"));
+    }
+
+    #[test]
+    fn rss_work() {
+        let rss = Rss { feed: feed() };
+        let document = rss
+            .render_changelog(&[release("v0.2.0", 1714653296), release("v0.1.0", 0)])
+            .unwrap();
+        assert!(document.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\">\n  <channel>\n    <title>Towers patch notes</title>\n    <link>https://example.com/notes</link>\n"));
+        assert!(document.contains("      <guid isPermaLink=\"false\">https://example.com/notes#v0-2-0</guid>\n      <pubDate>Thu, 02 May 2024 12:34:56 +0000</pubDate>\n"));
+        assert!(rss.insert(&document, &release("v0.1.0", 0)).is_err());
+        let removed = rss.remove(&document, &release("v0.2.0", 0)).unwrap();
+        assert_eq!(
+            removed,
+            rss.render_changelog(&[release("v0.1.0", 0)]).unwrap()
+        );
+        assert!(Rss {
+            feed: Feed::default()
+        }
+        .render(&release("v0.1.0", 0))
+        .is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use super::{Entry, Release, Renderer};
+
+/// Stylesheet embedded with `stylesheet = "default"`.
+pub const STYLESHEET: &str = "\
+body { font-family: system-ui, sans-serif; line-height: 1.5; max-width: 48rem; margin: 0 auto; padding: 1rem; }
+h1, h2, h3, h4 { line-height: 1.2; }
+h1 a, h2 a, h3 a, h4 a { color: inherit; text-decoration: none; }
+section.breaking { border-left: 4px solid #c0392b; padding-left: 1rem; }
+li.breaking { font-weight: bold; }
+p.breaking-note { font-weight: normal; margin: 0.25rem 0; white-space: pre-line; }
+";
+
+/// New releases are inserted right after this line of a changelog.
+const CHANGELOG_MARKER: &str = "<!-- dedma -->";
+
+/// The notes as a self-contained HTML page: one `<section>` per kind and scope, each
+/// with an `id` to link to, and the commit text escaped.
+#[derive(Default)]
+pub struct Html {
+    /// CSS embedded in the `<head>` of the page, none by default.
+    pub stylesheet: Option<String>,
+}
+
+impl Html {
+    pub fn with_stylesheet(stylesheet: &str) -> Html {
+        Html {
+            stylesheet: Some(stylesheet.to_string()),
+        }
+    }
+
+    /// Wraps `body` in a page titled `title`.
+    fn page(&self, language: &str, title: &str, body: &str) -> String {
+        let mut page = format!(
+            "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n",
+            escape(language),
+            escape(title)
+        );
+        if let Some(stylesheet) = &self.stylesheet {
+            page.push_str(&format!("<style>\n{}\n</style>\n", stylesheet.trim_end()));
+        }
+        page.push_str(&format!("</head>\n<body>\n{body}</body>\n</html>\n"));
+        page
+    }
+}
+
+/// Escapes the characters with a meaning in HTML text and attributes.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// An `id` made of the letters and digits of `text`: `v0.2.0` gives `v0-2-0`.
+pub(super) fn anchor(text: &str) -> String {
+    let mut anchor = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            anchor.push(c);
+        } else if !anchor.is_empty() && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+    anchor.trim_end_matches('-').to_string()
+}
+
+/// A heading of `level` linking to the `id` of its section.
+fn heading(level: usize, id: &str, text: &str) -> String {
+    format!(
+        "<h{level}><a href=\"#{id}\">{}</a></h{level}>\n",
+        escape(text)
+    )
+}
+
+fn list(entries: &[Entry], notes: bool) -> String {
+    let mut list = String::from("<ul>\n");
+    for entry in entries {
+        let class = if entry.breaking && !notes {
+            " class=\"breaking\""
+        } else {
+            ""
+        };
+        list.push_str(&format!(
+            "<li{class} data-commit=\"{}\">{}",
+            escape(&entry.hash),
+            escape(&entry.content)
+        ));
+        for reference in &entry.references {
+            match &reference.url {
+                Some(url) => list.push_str(&format!(
+                    " <a class=\"ref\" href=\"{}\">{}</a>",
+                    escape(url),
+                    escape(&reference.key)
+                )),
+                None => list.push_str(&format!(
+                    " <span class=\"ref\">{}</span>",
+                    escape(&reference.key)
+                )),
+            }
+        }
+        if let Some(url) = &entry.url {
+            list.push_str(&format!(
+                " <a class=\"commit\" href=\"{}\">{}</a>",
+                escape(url),
+                escape(entry.short_hash())
+            ));
+        }
+        if notes && !entry.breaking_note.is_empty() {
+            list.push_str(&format!(
+                "<p class=\"breaking-note\">{}</p>",
+                escape(&entry.breaking_note)
+            ));
+        }
+        list.push_str("</li>\n");
+    }
+    list.push_str("</ul>\n");
+    list
+}
+
+/// The `<article>` of a release. Its heading is of `level` and the `id`s are
+/// prefixed by `prefix`, so that several releases fit in one page.
+pub(super) fn article(release: &Release, level: usize, prefix: &str) -> String {
+    let id = anchor(&release.tag);
+    let mut article = format!("<article class=\"release\" id=\"{id}\">\n");
+    article.push_str(&heading(level, &id, &release.heading()));
+    if let (Some(url), Some(range)) = (&release.compare_url, &release.compare_range) {
+        article.push_str(&format!(
+            "<p class=\"compare\"><a href=\"{}\">{}</a></p>\n",
+            escape(url),
+            escape(range)
+        ));
+    }
+    if !release.breaking.is_empty() {
+        let id = format!("{prefix}breaking");
+        article.push_str(&format!("<section class=\"breaking\" id=\"{id}\">\n"));
+        article.push_str(&heading(level + 1, &id, &release.breaking_label));
+        article.push_str(&list(&release.breaking, true));
+        article.push_str("</section>\n");
+    }
+    for section in &release.sections {
+        let id = format!("{prefix}{}", anchor(&section.kind));
+        article.push_str(&format!("<section class=\"kind\" id=\"{id}\">\n"));
+        article.push_str(&heading(level + 1, &id, &section.label));
+        for scope in &section.scopes {
+            match &scope.label {
+                Some(label) => {
+                    let id = format!("{id}-{}", anchor(&scope.name));
+                    article.push_str(&format!("<section class=\"scope\" id=\"{id}\">\n"));
+                    article.push_str(&heading(level + 2, &id, label));
+                    article.push_str(&list(&scope.entries, false));
+                    article.push_str("</section>\n");
+                }
+                None => article.push_str(&list(&scope.entries, false)),
+            }
+        }
+        article.push_str("</section>\n");
+    }
+    article.push_str("</article>\n");
+    article
+}
+
+impl Renderer for Html {
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let body = article(release, 1, "");
+        Ok(self.page(&release.language, &release.heading(), &body))
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let mut body = format!("{CHANGELOG_MARKER}\n");
+        for release in releases {
+            body.push_str(&article(release, 2, &format!("{}-", anchor(&release.tag))));
+        }
+        let (language, title) = match releases.first() {
+            Some(release) => (release.language.as_str(), release.heading()),
+            None => ("en", String::new()),
+        };
+        Ok(self.page(language, &title, &body))
+    }
+
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return self.render_changelog(std::slice::from_ref(release));
+        }
+        let id = anchor(&release.tag);
+        if document.contains(&format!("<article class=\"release\" id=\"{id}\">")) {
+            anyhow::bail!(release.message("messages.already_in_changelog"));
+        }
+        let Some(marker) = document.find(CHANGELOG_MARKER) else {
+            anyhow::bail!(
+                "The changelog has no '{CHANGELOG_MARKER}' line to insert the notes after"
+            );
+        };
+        let position = marker + CHANGELOG_MARKER.len() + 1;
+        let position = position.min(document.len());
+        let article = article(release, 2, &format!("{id}-"));
+        Ok(format!(
+            "{}{article}{}",
+            &document[..position],
+            &document[position..]
+        ))
+    }
+
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let start = format!(
+            "<article class=\"release\" id=\"{}\">",
+            anchor(&release.tag)
+        );
+        let Some(start) = document.find(&start) else {
+            return Ok(document.to_string());
+        };
+        let end = document[start..]
+            .find("</article>\n")
+            .map_or(document.len(), |end| start + end + "</article>\n".len());
+        Ok(format!("{}{}", &document[..start], &document[end..]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Reference, Scope, Section};
+
+    fn release(tag: &str) -> Release {
+        Release {
+            tag: tag.to_string(),
+            language: String::from("en"),
+            sections: vec![Section {
+                kind: String::from("feat"),
+                label: String::from("New features"),
+                category: None,
+                scopes: vec![
+                    Scope {
+                        name: String::from("ui"),
+                        label: Some(String::from("UI")),
+                        entries: vec![Entry {
+                            content: String::from("<b>bold</b> & \"quoted\" names"),
+                            hash: String::from("7e51b36"),
+                            ..Default::default()
+                        }],
+                    },
+                    Scope {
+                        name: String::from("other"),
+                        label: None,
+                        entries: vec![Entry {
+                            content: String::from("faster loading"),
+                            hash: String::from("37b0781"),
+                            references: vec![Reference {
+                                key: String::from("#12"),
+                                url: Some(String::from("https://example.com/issues/12")),
+                                ..Default::default()
+                            }],
+                            ..Default::default()
+                        }],
+                    },
+                ],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn html_work() {
+        let page = Html::default().render(&release("v0.2.0")).unwrap();
+        let body = "\
+<body>
+<article class=\"release\" id=\"v0-2-0\">
+<h1><a href=\"#v0-2-0\">v0.2.0</a></h1>
+<section class=\"kind\" id=\"feat\">
+<h2><a href=\"#feat\">New features</a></h2>
+<section class=\"scope\" id=\"feat-ui\">
+<h3><a href=\"#feat-ui\">UI</a></h3>
+<ul>
+<li data-commit=\"7e51b36\">&lt;b&gt;bold&lt;/b&gt; &amp; &quot;quoted&quot; names</li>
+</ul>
+</section>
+<ul>
+<li data-commit=\"37b0781\">faster loading <a class=\"ref\" href=\"https://example.com/issues/12\">#12</a></li>
+</ul>
+</section>
+</article>
+</body>
+";
+        assert!(page.starts_with("<!DOCTYPE html>\n<html lang=\"en\">"));
+        assert!(page.contains(body));
+        assert!(!page.contains("<style>"));
+        assert!(Html::with_stylesheet(STYLESHEET)
+            .render(&release("v0.2.0"))
+            .unwrap()
+            .contains("<style>\nbody {"));
+
+        let changelog = Html::default().insert("", &release("v0.1.0")).unwrap();
+        let changelog = Html::default()
+            .insert(&changelog, &release("v0.2.0"))
+            .unwrap();
+        let newest = changelog.find("id=\"v0-2-0\"").unwrap();
+        assert!(newest < changelog.find("id=\"v0-1-0\"").unwrap());
+        assert!(changelog.contains("<section class=\"kind\" id=\"v0-2-0-feat\">"));
+        assert!(Html::default()
+            .insert(&changelog, &release("v0.2.0"))
+            .is_err());
+
+        let removed = Html::default()
+            .remove(&changelog, &release("v0.2.0"))
+            .unwrap();
+        assert!(!removed.contains("id=\"v0-2-0\""));
+        assert_eq!(
+            removed,
+            Html::default().insert("", &release("v0.1.0")).unwrap()
+        );
+    }
+}
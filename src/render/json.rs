@@ -0,0 +1,249 @@
+use super::{Reference, Release, Renderer};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Version of the documents written by [`Json`]. It changes only when a field is
+/// removed or changes meaning, new fields can be added without changing it.
+pub const JSON_VERSION: u32 = 1;
+
+/// The notes as a JSON document, for the tools reading them programmatically.
+pub struct Json;
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    #[serde(flatten)]
+    release: JsonRelease<'a>,
+}
+
+#[derive(Serialize)]
+struct Changelog<'a> {
+    version: u32,
+    releases: Vec<JsonRelease<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonRelease<'a> {
+    tag: &'a str,
+    previous_tag: Option<&'a str>,
+    date: Option<&'a str>,
+    language: &'a str,
+    compare_url: Option<&'a str>,
+    kinds: Vec<JsonKind<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonKind<'a> {
+    kind: &'a str,
+    label: &'a str,
+    entries: Vec<JsonEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    /// `None` for the commits without a scope.
+    scope: Option<&'a str>,
+    description: &'a str,
+    hash: &'a str,
+    authors: &'a [String],
+    breaking: bool,
+    breaking_note: Option<&'a str>,
+    refs: &'a [String],
+    references: &'a [Reference],
+    url: Option<&'a str>,
+}
+
+impl<'a> From<&'a Release> for JsonRelease<'a> {
+    fn from(release: &'a Release) -> Self {
+        let mut kinds = vec![];
+        for section in &release.sections {
+            let mut entries = vec![];
+            for scope in &section.scopes {
+                for entry in &scope.entries {
+                    entries.push(JsonEntry {
+                        scope: scope.label.as_ref().map(|_| scope.name.as_str()),
+                        description: &entry.content,
+                        hash: &entry.hash,
+                        authors: &entry.authors,
+                        breaking: entry.breaking,
+                        breaking_note: Some(entry.breaking_note.as_str())
+                            .filter(|note| !note.is_empty()),
+                        refs: &entry.refs,
+                        references: &entry.references,
+                        url: entry.url.as_deref(),
+                    });
+                }
+            }
+            kinds.push(JsonKind {
+                kind: &section.kind,
+                label: &section.label,
+                entries,
+            });
+        }
+        JsonRelease {
+            tag: &release.tag,
+            previous_tag: release.previous_tag.as_deref(),
+            date: release.date.as_deref(),
+            language: &release.language,
+            compare_url: release.compare_url.as_deref(),
+            kinds,
+        }
+    }
+}
+
+impl Renderer for Json {
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let document = Document {
+            version: JSON_VERSION,
+            release: release.into(),
+        };
+        Ok(serde_json::to_string_pretty(&document)? + "\n")
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let changelog = Changelog {
+            version: JSON_VERSION,
+            releases: releases.iter().map(JsonRelease::from).collect(),
+        };
+        Ok(serde_json::to_string_pretty(&changelog)? + "\n")
+    }
+
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return self.render_changelog(std::slice::from_ref(release));
+        }
+        let mut changelog = parse_changelog(document)?;
+        let releases = releases_of(&mut changelog);
+        if releases.iter().any(|known| known["tag"] == release.tag) {
+            anyhow::bail!(release.message("messages.already_in_changelog"));
+        }
+        releases.insert(0, serde_json::to_value(JsonRelease::from(release))?);
+        Ok(serde_json::to_string_pretty(&changelog)? + "\n")
+    }
+
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return Ok(document.to_string());
+        }
+        let mut changelog = parse_changelog(document)?;
+        let releases = releases_of(&mut changelog);
+        if !releases.iter().any(|known| known["tag"] == release.tag) {
+            return Ok(document.to_string());
+        }
+        releases.retain(|known| known["tag"] != release.tag);
+        Ok(serde_json::to_string_pretty(&changelog)? + "\n")
+    }
+}
+
+/// A changelog written by [`Json::render_changelog`], checked to have a list of releases.
+fn parse_changelog(document: &str) -> anyhow::Result<Value> {
+    let changelog: Value = serde_json::from_str(document)
+        .map_err(|error| anyhow::anyhow!("The changelog is not valid JSON: {error}"))?;
+    if changelog["version"] != JSON_VERSION {
+        anyhow::bail!("The changelog is not a version {JSON_VERSION} dedma changelog");
+    }
+    if !changelog["releases"].is_array() {
+        anyhow::bail!("The changelog has no 'releases' list");
+    }
+    Ok(changelog)
+}
+
+/// The releases of a changelog checked by [`parse_changelog`].
+fn releases_of(changelog: &mut Value) -> &mut Vec<Value> {
+    changelog["releases"]
+        .as_array_mut()
+        .expect("the changelog was checked")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Entry, Scope, Section};
+
+    fn release(tag: &str) -> Release {
+        let entry = Entry {
+            content: String::from("one more tower type"),
+            hash: String::from("7e51b36"),
+            authors: vec![String::from("Ada")],
+            breaking: true,
+            breaking_note: String::from("saves from 0.1 can't be loaded"),
+            refs: vec![String::from("#12")],
+            references: vec![Reference {
+                key: String::from("#12"),
+                closes: true,
+                url: Some(String::from("https://example.com/issues/12")),
+            }],
+            url: Some(String::from("https://example.com/commit/7e51b36")),
+        };
+        Release {
+            tag: tag.to_string(),
+            previous_tag: Some(String::from("v0.1.0")),
+            date: Some(String::from("2024-05-01")),
+            language: String::from("en"),
+            breaking: vec![entry.clone()],
+            sections: vec![Section {
+                kind: String::from("feat"),
+                label: String::from("New features"),
+                category: None,
+                scopes: vec![Scope {
+                    name: String::from("towers"),
+                    label: Some(String::from("Towers")),
+                    entries: vec![entry],
+                }],
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn json_work() {
+        let document: Value =
+            serde_json::from_str(&Json.render(&release("v0.2.0")).unwrap()).unwrap();
+        assert_eq!(
+            document,
+            serde_json::json!({
+                "version": 1,
+                "tag": "v0.2.0",
+                "previous_tag": "v0.1.0",
+                "date": "2024-05-01",
+                "language": "en",
+                "compare_url": null,
+                "kinds": [{
+                    "kind": "feat",
+                    "label": "New features",
+                    "entries": [{
+                        "scope": "towers",
+                        "description": "one more tower type",
+                        "hash": "7e51b36",
+                        "authors": ["Ada"],
+                        "breaking": true,
+                        "breaking_note": "saves from 0.1 can't be loaded",
+                        "refs": ["#12"],
+                        "references": [{
+                            "key": "#12",
+                            "closes": true,
+                            "url": "https://example.com/issues/12",
+                        }],
+                        "url": "https://example.com/commit/7e51b36",
+                    }],
+                }],
+            })
+        );
+
+        let changelog = Json.insert("", &release("v0.1.0")).unwrap();
+        let changelog = Json.insert(&changelog, &release("v0.2.0")).unwrap();
+        let releases: Value = serde_json::from_str(&changelog).unwrap();
+        assert_eq!(releases["releases"][0]["tag"], "v0.2.0");
+        assert_eq!(releases["releases"][1]["tag"], "v0.1.0");
+        assert!(Json.insert(&changelog, &release("v0.2.0")).is_err());
+
+        let removed: Value =
+            serde_json::from_str(&Json.remove(&changelog, &release("v0.2.0")).unwrap()).unwrap();
+        assert_eq!(removed["releases"].as_array().unwrap().len(), 1);
+        assert_eq!(removed["releases"][0]["tag"], "v0.1.0");
+    }
+}
@@ -0,0 +1,301 @@
+use super::{Category, Release, Renderer};
+
+/// Top of the documents written by `dedma changelog --format keepachangelog`.
+const HEADER: &str = "\
+# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/).
+";
+
+/// The notes as in keepachangelog.com: a `## [version] - date` heading, the kinds
+/// gathered into its categories and the compare links at the bottom.
+pub struct KeepAChangelog;
+
+fn heading(release: &Release) -> String {
+    match &release.date {
+        Some(date) => format!("## [{}] - {date}", release.version()),
+        None => format!("## [{}]", release.version()),
+    }
+}
+
+/// The link definition of the heading, when the compare page is known.
+fn link(release: &Release) -> Option<String> {
+    let url = release.compare_url.as_ref()?;
+    Some(format!("[{}]: {url}", release.version()))
+}
+
+fn is_link(line: &&str) -> bool {
+    line.starts_with('[') && line.contains("]: ")
+}
+
+/// The line of the heading of `release` in `lines` and the end of its section, which
+/// stops at the next release or at the links.
+fn find_section(lines: &[&str], release: &Release) -> Option<(usize, usize)> {
+    let title = format!("## [{}]", release.version());
+    let start = lines
+        .iter()
+        .position(|line| *line == title || line.starts_with(&format!("{title} ")))?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| line.starts_with("## [") || is_link(line))
+        .map_or(lines.len(), |end| start + 1 + end);
+    Some((start, end))
+}
+
+/// The heading and categories of a release, without its link.
+fn section(release: &Release) -> String {
+    let mut section = format!("{}\n", heading(release));
+    let mut categories: Vec<Category> = release
+        .sections
+        .iter()
+        .filter_map(|section| section.category)
+        .collect();
+    categories.sort();
+    categories.dedup();
+    for category in categories {
+        section.push_str(&format!("\n### {category:?}\n"));
+        for kind in &release.sections {
+            if kind.category != Some(category) {
+                continue;
+            }
+            for scope in &kind.scopes {
+                for entry in &scope.entries {
+                    section.push_str("- ");
+                    if entry.breaking {
+                        section.push_str(&format!("**{}:** ", release.breaking_label));
+                    }
+                    if let Some(label) = &scope.label {
+                        section.push_str(&format!("{label}: "));
+                    }
+                    section.push_str(&entry.content);
+                    section.push('\n');
+                }
+            }
+        }
+    }
+    section
+}
+
+impl Renderer for KeepAChangelog {
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let mut notes = section(release);
+        if let Some(link) = link(release) {
+            notes.push_str(&format!("\n{link}\n"));
+        }
+        Ok(notes)
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let mut document = String::from(HEADER);
+        for release in releases {
+            document.push_str(&format!("\n{}", section(release)));
+        }
+        let links: Vec<String> = releases.iter().filter_map(link).collect();
+        if !links.is_empty() {
+            document.push_str(&format!("\n{}\n", links.join("\n")));
+        }
+        Ok(document)
+    }
+
+    /// Puts the release above the newest one, below `[Unreleased]`, and its link
+    /// above the other links. An empty section of the release, such as the
+    /// `## [Unreleased]` heading of Keep a Changelog, is filled.
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        if document.trim().is_empty() {
+            return self.render_changelog(std::slice::from_ref(release));
+        }
+        let mut lines: Vec<&str> = document.lines().collect();
+        if let Some((start, end)) = find_section(&lines, release) {
+            if lines[start + 1..end]
+                .iter()
+                .any(|line| !line.trim().is_empty())
+            {
+                anyhow::bail!(release.message("messages.already_in_changelog"));
+            }
+            lines.drain(start..end);
+            let label = format!("[{}]: ", release.version());
+            lines.retain(|line| !line.starts_with(&label));
+        }
+
+        let first_link = lines.iter().position(is_link);
+        let before_links = first_link.unwrap_or(lines.len());
+        let position = lines[..before_links]
+            .iter()
+            .position(|line| line.starts_with("## [") && !line.starts_with("## [Unreleased]"))
+            .unwrap_or(before_links);
+
+        let mut result = String::new();
+        for (index, line) in lines.iter().enumerate() {
+            if index == position {
+                if !result.ends_with("\n\n") {
+                    result.push('\n');
+                }
+                result.push_str(&section(release));
+                result.push('\n');
+            }
+            if Some(index) == first_link {
+                if let Some(link) = link(release) {
+                    result.push_str(&format!("{link}\n"));
+                }
+            }
+            result.push_str(line);
+            result.push('\n');
+        }
+        if position == lines.len() {
+            result.push('\n');
+            result.push_str(&section(release));
+        }
+        if first_link.is_none() {
+            if let Some(link) = link(release) {
+                result.push_str(&format!("\n{link}\n"));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Empties the section of the release, keeping its heading and its link as Keep a
+    /// Changelog does for `[Unreleased]`.
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let lines: Vec<&str> = document.lines().collect();
+        let Some((start, end)) = find_section(&lines, release) else {
+            return Ok(document.to_string());
+        };
+        let mut result = String::new();
+        for line in &lines[..=start] {
+            result.push_str(line);
+            result.push('\n');
+        }
+        if end < lines.len() {
+            result.push('\n');
+        }
+        for line in &lines[end..] {
+            result.push_str(line);
+            result.push('\n');
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Entry, Scope, Section};
+
+    fn release(tag: &str, date: Option<&str>, compare: &str) -> Release {
+        let entry = |content: &str, breaking: bool| Entry {
+            content: content.to_string(),
+            breaking,
+            ..Default::default()
+        };
+        let section = |kind: &str, category: Option<Category>, entries: Vec<Entry>| Section {
+            kind: kind.to_string(),
+            label: kind.to_string(),
+            category,
+            scopes: vec![Scope {
+                name: String::from("other"),
+                label: None,
+                entries,
+            }],
+        };
+        Release {
+            tag: tag.to_string(),
+            date: date.map(str::to_string),
+            breaking_label: String::from("Breaking"),
+            compare_url: Some(format!("https://example.com/compare/{compare}")),
+            sections: vec![
+                section(
+                    "feat",
+                    Some(Category::Added),
+                    vec![entry("settings", false)],
+                ),
+                section("fix", Some(Category::Fixed), vec![entry("crash", false)]),
+                section("chore", None, vec![entry("bump", false)]),
+                section(
+                    "perf",
+                    Some(Category::Changed),
+                    vec![entry("new save", true)],
+                ),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn keep_a_changelog_work() {
+        let notes = "\
+## [0.2.0] - 2024-05-01
+
+### Added
+- settings
+
+### Changed
+- **Breaking:** new save
+
+### Fixed
+- crash
+
+[0.2.0]: https://example.com/compare/v0.1.0...v0.2.0
+";
+        let v2 = release("v0.2.0", Some("2024-05-01"), "v0.1.0...v0.2.0");
+        assert_eq!(KeepAChangelog.render(&v2).unwrap(), notes);
+
+        let document = "\
+# Changelog
+
+## [Unreleased]
+
+## [0.1.0] - 2024-03-12
+
+### Added
+- first release
+
+[Unreleased]: https://example.com/compare/v0.1.0...HEAD
+";
+        let result = KeepAChangelog.insert(document, &v2).unwrap();
+        let expected = "\
+# Changelog
+
+## [Unreleased]
+
+## [0.2.0] - 2024-05-01
+
+### Added
+- settings
+
+### Changed
+- **Breaking:** new save
+
+### Fixed
+- crash
+
+## [0.1.0] - 2024-03-12
+
+### Added
+- first release
+
+[0.2.0]: https://example.com/compare/v0.1.0...v0.2.0
+[Unreleased]: https://example.com/compare/v0.1.0...HEAD
+";
+        assert_eq!(result, expected);
+        assert!(KeepAChangelog.insert(&result, &v2).is_err());
+
+        let unreleased = release("Unreleased", None, "v0.2.0...HEAD");
+        let previewed = KeepAChangelog.insert(&result, &unreleased).unwrap();
+        assert!(previewed.starts_with("# Changelog\n\n## [Unreleased]\n\n### Added\n- settings\n"));
+        assert!(previewed.contains("[Unreleased]: https://example.com/compare/v0.2.0...HEAD\n"));
+        assert!(!previewed.contains("v0.1.0...HEAD"));
+        assert!(KeepAChangelog.insert(&previewed, &unreleased).is_err());
+        let emptied = KeepAChangelog.remove(&previewed, &unreleased).unwrap();
+        assert!(emptied.starts_with("# Changelog\n\n## [Unreleased]\n\n## [0.2.0] - 2024-05-01\n"));
+        assert_eq!(
+            KeepAChangelog.insert(&emptied, &unreleased).unwrap(),
+            previewed
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use super::{Entry, Release, Renderer};
+
+/// The notes as Markdown, one `#` heading per kind and `##` per scope.
+pub struct Markdown;
+
+/// An item of a list, followed by its references and by the short hash of the commit,
+/// each linking to its page when it is known.
+fn item(entry: &Entry) -> String {
+    let mut links: Vec<String> = entry
+        .references
+        .iter()
+        .map(|reference| match &reference.url {
+            Some(url) => format!("[{}]({url})", reference.key),
+            None => reference.key.clone(),
+        })
+        .collect();
+    if let Some(url) = &entry.url {
+        links.push(format!("[{}]({url})", entry.short_hash()));
+    }
+    if links.is_empty() {
+        format!("- {}\n", entry.content)
+    } else {
+        format!("- {} ({})\n", entry.content, links.join(", "))
+    }
+}
+
+impl Renderer for Markdown {
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let mut notes = String::new();
+        if let (Some(url), Some(range)) = (&release.compare_url, &release.compare_range) {
+            notes.push_str(&format!("[{range}]({url})\n"));
+        }
+        if !release.breaking.is_empty() {
+            notes.push_str(&format!("# {}\n", release.breaking_label));
+            for entry in &release.breaking {
+                notes.push_str(&item(entry));
+                for line in entry.breaking_note.lines() {
+                    notes.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+        for section in &release.sections {
+            notes.push_str(&format!("# {}\n", section.label));
+            for scope in &section.scopes {
+                if let Some(label) = &scope.label {
+                    notes.push_str(&format!("## {label}\n"));
+                }
+                for entry in &scope.entries {
+                    notes.push_str(&item(entry));
+                }
+            }
+        }
+        Ok(notes)
+    }
+
+    fn render_section(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(release_section(&release.heading(), &self.render(release)?))
+    }
+
+    fn render_changelog(&self, releases: &[Release]) -> anyhow::Result<String> {
+        let sections = releases
+            .iter()
+            .map(|release| self.render_section(release))
+            .collect::<anyhow::Result<Vec<String>>>()?;
+        Ok(format!("{CHANGELOG_MARKER}\n{}", sections.join("\n")))
+    }
+
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        insert_section(document, release, &self.render_section(release)?)
+    }
+
+    fn remove(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        Ok(remove_section(document, &release.tag))
+    }
+}
+
+/// Puts `notes` under a `# {heading}` title, moving their own headings one level down.
+fn release_section(heading: &str, notes: &str) -> String {
+    let mut section = format!("# {heading}\n");
+    for line in notes.lines() {
+        if line.starts_with('#') {
+            section.push('#');
+        }
+        section.push_str(line);
+        section.push('\n');
+    }
+    section
+}
+
+/// Whether `line` is the heading of the section of `tag`, as in `# v0.2.0 (2024-05-01)`.
+fn is_heading_of(line: &str, tag: &str) -> bool {
+    let heading = line.trim_start_matches('#').trim();
+    line.starts_with('#') && (heading == tag || heading.starts_with(&format!("{tag} (")))
+}
+
+/// `document` without the `# {tag}` section, which ends at the next release.
+fn remove_section(document: &str, tag: &str) -> String {
+    if !document
+        .lines()
+        .any(|line| line.starts_with("# ") && is_heading_of(line, tag))
+    {
+        return document.to_string();
+    }
+    let mut result = String::new();
+    let mut removing = false;
+    for line in document.lines() {
+        if line.starts_with("# ") {
+            removing = is_heading_of(line, tag);
+        }
+        if !removing {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    while result.ends_with("\n\n") {
+        result.pop();
+    }
+    result
+}
+
+/// New release sections are inserted right after this line of a changelog.
+const CHANGELOG_MARKER: &str = "<!-- dedma -->";
+
+/// Inserts `section` after the marker comment, or below the title when there is none.
+/// Everything else in `document` is kept as it is.
+fn insert_section(document: &str, release: &Release, section: &str) -> anyhow::Result<String> {
+    if document
+        .lines()
+        .any(|line| is_heading_of(line, &release.tag))
+    {
+        anyhow::bail!(release.message("messages.already_in_changelog"));
+    }
+    if document.trim().is_empty() {
+        return Ok(format!("{CHANGELOG_MARKER}\n{section}"));
+    }
+
+    let lines: Vec<&str> = document.lines().collect();
+    let position = match lines
+        .iter()
+        .position(|line| line.trim() == CHANGELOG_MARKER)
+    {
+        Some(marker) => marker + 1,
+        None => match lines.iter().position(|line| !line.trim().is_empty()) {
+            Some(title) if lines[title].starts_with("# ") => title + 1,
+            _ => 0,
+        },
+    };
+
+    let mut result = String::new();
+    for line in &lines[..position] {
+        result.push_str(line);
+        result.push('\n');
+    }
+    if position > 0 && lines[position - 1].starts_with("# ") {
+        result.push('\n');
+    }
+    result.push_str(section);
+    let rest = &lines[position..];
+    if rest.first().is_some_and(|line| !line.trim().is_empty()) {
+        result.push('\n');
+    }
+    for line in rest {
+        result.push_str(line);
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Reference, Scope, Section};
+
+    #[test]
+    fn links_work() {
+        let release = Release {
+            tag: String::from("v0.2.0"),
+            previous_tag: Some(String::from("v0.1.0")),
+            compare_range: Some(String::from("v0.1.0...v0.2.0")),
+            compare_url: Some(String::from("https://example.com/compare/v0.1.0...v0.2.0")),
+            sections: vec![Section {
+                label: String::from("New features"),
+                scopes: vec![Scope {
+                    entries: vec![Entry {
+                        content: String::from("added one tower type"),
+                        hash: String::from("eb816b1c22d09726a0975db7275402625e14a7cf"),
+                        url: Some(String::from("https://example.com/commit/eb816b1")),
+                        references: vec![
+                            Reference {
+                                key: String::from("#12"),
+                                url: Some(String::from("https://example.com/issues/12")),
+                                ..Default::default()
+                            },
+                            Reference {
+                                key: String::from("owner/other#9"),
+                                ..Default::default()
+                            },
+                        ],
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let notes = "\
+[v0.1.0...v0.2.0](https://example.com/compare/v0.1.0...v0.2.0)
+# New features
+- added one tower type ([#12](https://example.com/issues/12), owner/other#9, [eb816b1](https://example.com/commit/eb816b1))
+";
+        assert_eq!(Markdown.render(&release).unwrap(), notes);
+    }
+
+    #[test]
+    fn release_section_work() {
+        let notes = "# New features\n## UI\n- added a settings screen\n";
+        let section = "# Unreleased\n## New features\n### UI\n- added a settings screen\n";
+        assert_eq!(release_section("Unreleased", notes), section);
+    }
+
+    #[test]
+    fn insert_section_work() {
+        let release = |tag: &str| Release {
+            tag: tag.to_string(),
+            ..Default::default()
+        };
+        let section = "# v0.2.0 (2024-05-01)\n## New features\n- added one tower type\n";
+        let changelog = "\
+# Changelog
+Hand-written introduction.
+<!-- dedma -->
+# v0.1.0 (2024-03-12)
+## Bug fix
+- fixed release notes path
+";
+        let result = "\
+# Changelog
+Hand-written introduction.
+<!-- dedma -->
+# v0.2.0 (2024-05-01)
+## New features
+- added one tower type
+
+# v0.1.0 (2024-03-12)
+## Bug fix
+- fixed release notes path
+";
+        assert_eq!(
+            insert_section(changelog, &release("v0.2.0"), section).unwrap(),
+            result
+        );
+        let error = insert_section(changelog, &release("v0.1.0"), section).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The notes of 'v0.1.0' are already in the changelog"
+        );
+
+        let titled = "# Changelog\n\nOlder notes.\n";
+        let result = "\
+# Changelog
+
+# v0.2.0 (2024-05-01)
+## New features
+- added one tower type
+
+Older notes.
+";
+        assert_eq!(
+            insert_section(titled, &release("v0.2.0"), section).unwrap(),
+            result
+        );
+    }
+
+    #[test]
+    fn remove_section_work() {
+        let changelog = "\
+<!-- dedma -->
+# Unreleased
+## Bug fix
+- fixed release notes path
+
+# v0.1.0 (2024-03-12)
+## New features
+- added one tower type
+";
+        let result = "\
+<!-- dedma -->
+# v0.1.0 (2024-03-12)
+## New features
+- added one tower type
+";
+        assert_eq!(remove_section(changelog, "Unreleased"), result);
+        assert_eq!(
+            remove_section(changelog, "v0.1.0"),
+            "<!-- dedma -->\n# Unreleased\n## Bug fix\n- fixed release notes path\n"
+        );
+        assert_eq!(remove_section(result, "Unreleased"), result);
+    }
+}
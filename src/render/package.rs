@@ -0,0 +1,333 @@
+use super::time::{release_time, rfc2822_date, rpm_date};
+use super::{Entry, Release, Renderer};
+use serde::Deserialize;
+
+/// The `[package]` section of `.dedma.toml`, used by the Debian and RPM changelogs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Package {
+    /// Name of the package, the name of the repository by default.
+    pub name: Option<String>,
+    /// `Name <email>`, from `user.name` and `user.email` of git by default.
+    pub maintainer: Option<String>,
+    /// Debian distribution the package is uploaded to.
+    pub distribution: String,
+    /// Debian urgency of the upload.
+    pub urgency: String,
+    /// Revision of the packaging, written after the version as in `1.2.0-1`.
+    /// Empty for Debian native packages.
+    pub revision: String,
+}
+
+impl Default for Package {
+    fn default() -> Self {
+        Package {
+            name: None,
+            maintainer: None,
+            distribution: String::from("unstable"),
+            urgency: String::from("medium"),
+            revision: String::from("1"),
+        }
+    }
+}
+
+impl Package {
+    fn name(&self) -> anyhow::Result<&str> {
+        match &self.name {
+            Some(name) => Ok(name),
+            None => anyhow::bail!("Set `name` in the [package] section of .dedma.toml"),
+        }
+    }
+
+    fn maintainer(&self) -> anyhow::Result<&str> {
+        match &self.maintainer {
+            Some(maintainer) => Ok(maintainer),
+            None => anyhow::bail!(
+                "Set `maintainer` in the [package] section of .dedma.toml, or user.name and user.email in git"
+            ),
+        }
+    }
+
+    /// The version of the package: `1.2.0-rc1` gives `1.2.0~rc1-1`, so that the
+    /// pre-releases come before the release. Unreleased commits get the version of
+    /// the previous release followed by `+unreleased`.
+    fn version(&self, release: &Release) -> anyhow::Result<String> {
+        let mut version = release.version().to_string();
+        if !version.starts_with(|c: char| c.is_ascii_digit()) {
+            let previous = release.previous_tag.as_deref().map(|tag| Release {
+                tag: tag.to_string(),
+                ..Default::default()
+            });
+            match previous {
+                Some(previous) if previous.version().starts_with(|c: char| c.is_ascii_digit()) => {
+                    let suffix: String = version
+                        .to_lowercase()
+                        .chars()
+                        .filter(char::is_ascii_alphanumeric)
+                        .collect();
+                    version = format!("{}+{suffix}", previous.version());
+                }
+                _ => anyhow::bail!("'{}' is not a package version", release.tag),
+            }
+        }
+        let version = version.replace('-', "~");
+        if self.revision.is_empty() {
+            return Ok(version);
+        }
+        Ok(format!("{version}-{}", self.revision))
+    }
+}
+
+/// The text of an entry: its scope then its description.
+fn entry_text(release: &Release, scope: Option<&str>, entry: &Entry) -> String {
+    let mut text = String::new();
+    if entry.breaking {
+        text.push_str(&format!("{}: ", release.breaking_label));
+    }
+    if let Some(scope) = scope {
+        text.push_str(&format!("{scope}: "));
+    }
+    text.push_str(&entry.content);
+    text
+}
+
+/// `text` cut at the spaces into lines of at most 80 characters, the first starting
+/// with `bullet` and the others with as many spaces.
+fn bullet_lines(bullet: &str, text: &str) -> String {
+    let indent = " ".repeat(bullet.chars().count());
+    let mut lines = String::new();
+    let mut line = bullet.to_string();
+    for word in text.split_whitespace() {
+        let start = line.chars().count() == indent.len();
+        if !start && line.chars().count() + 1 + word.chars().count() > 80 {
+            lines.push_str(&line);
+            lines.push('\n');
+            line = indent.clone();
+        } else if !start {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push_str(&line);
+    lines.push('\n');
+    lines
+}
+
+/// Every entry of the release, in the order of the sections.
+fn changes(release: &Release, bullet: &str) -> String {
+    let mut changes = String::new();
+    for section in &release.sections {
+        for scope in &section.scopes {
+            for entry in &scope.entries {
+                let text = entry_text(release, scope.label.as_deref(), entry);
+                changes.push_str(&bullet_lines(bullet, &text));
+            }
+        }
+    }
+    changes
+}
+
+/// A stanza of `debian/changelog`.
+pub struct Debian {
+    pub package: Package,
+}
+
+impl Renderer for Debian {
+    fn extension(&self) -> &str {
+        "debian"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(format!(
+            "{} ({}) {}; urgency={}\n\n{}\n -- {}  {}\n",
+            self.package.name()?,
+            self.package.version(release)?,
+            self.package.distribution,
+            self.package.urgency,
+            changes(release, "  * "),
+            self.package.maintainer()?,
+            rfc2822_date(release_time(release))
+        ))
+    }
+
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let stanza = self.render(release)?;
+        let header = format!(
+            "{} ({}) ",
+            self.package.name()?,
+            self.package.version(release)?
+        );
+        if document.lines().any(|line| line.starts_with(&header)) {
+            anyhow::bail!(release.message("messages.already_in_changelog"));
+        }
+        if document.trim().is_empty() {
+            return Ok(stanza);
+        }
+        Ok(format!("{stanza}\n{document}"))
+    }
+}
+
+/// An entry of the `%changelog` of an RPM spec file.
+pub struct Rpm {
+    pub package: Package,
+}
+
+impl Rpm {
+    fn header(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(format!(
+            "* {} {} - {}",
+            rpm_date(release_time(release)),
+            self.package.maintainer()?,
+            self.package.version(release)?
+        ))
+    }
+}
+
+impl Renderer for Rpm {
+    fn extension(&self) -> &str {
+        "rpm"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(format!(
+            "{}\n{}",
+            self.header(release)?,
+            changes(release, "- ")
+        ))
+    }
+
+    /// Puts the entry right after the `%changelog` line, or at the top without one.
+    fn insert(&self, document: &str, release: &Release) -> anyhow::Result<String> {
+        let version = format!(" - {}", self.package.version(release)?);
+        if document
+            .lines()
+            .any(|line| line.starts_with("* ") && line.ends_with(&version))
+        {
+            anyhow::bail!(release.message("messages.already_in_changelog"));
+        }
+        let entry = self.render(release)?;
+        let Some(marker) = document
+            .lines()
+            .position(|line| line.trim() == "%changelog")
+        else {
+            if document.trim().is_empty() {
+                return Ok(entry);
+            }
+            return Ok(format!("{entry}\n{document}"));
+        };
+        let mut result = String::new();
+        for (index, line) in document.lines().enumerate() {
+            result.push_str(line);
+            result.push('\n');
+            if index == marker {
+                result.push_str(&entry);
+                if index + 1 < document.lines().count() {
+                    result.push('\n');
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Scope, Section};
+
+    fn release(tag: &str) -> Release {
+        let long = "a description long enough to go past the eighty characters of a changelog line";
+        Release {
+            tag: tag.to_string(),
+            previous_tag: Some(String::from("v1.1.0")),
+            // 2024-05-02 12:34:56 UTC
+            time: Some(1714653296),
+            sections: vec![Section {
+                kind: String::from("fix"),
+                scopes: vec![Scope {
+                    name: String::from("ui"),
+                    label: Some(String::from("UI")),
+                    entries: vec![
+                        Entry {
+                            content: String::from("menu crash"),
+                            ..Default::default()
+                        },
+                        Entry {
+                            content: long.to_string(),
+                            ..Default::default()
+                        },
+                    ],
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn package() -> Package {
+        Package {
+            name: Some(String::from("towers")),
+            maintainer: Some(String::from("Ada <ada@example.com>")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn debian_work() {
+        let debian = Debian { package: package() };
+        let stanza = "\
+towers (1.2.0~rc1-1) unstable; urgency=medium
+
+  * UI: menu crash
+  * UI: a description long enough to go past the eighty characters of a
+    changelog line
+
+ -- Ada <ada@example.com>  Thu, 02 May 2024 12:34:56 +0000
+";
+        assert_eq!(debian.render(&release("v1.2.0-rc1")).unwrap(), stanza);
+        assert!(debian
+            .insert(stanza, &release("v1.2.0-rc1"))
+            .unwrap_err()
+            .to_string()
+            .contains("already"));
+        let unreleased = debian.insert(stanza, &release("Unreleased")).unwrap();
+        assert!(unreleased.starts_with("towers (1.1.0+unreleased-1) unstable;"));
+        assert!(unreleased.ends_with(stanza));
+
+        let native = Debian {
+            package: Package {
+                revision: String::new(),
+                ..package()
+            },
+        };
+        assert!(native
+            .render(&release("v1.2.0"))
+            .unwrap()
+            .starts_with("towers (1.2.0) unstable;"));
+        assert!(Debian {
+            package: Package::default()
+        }
+        .render(&release("v1.2.0"))
+        .is_err());
+    }
+
+    #[test]
+    fn rpm_work() {
+        let rpm = Rpm { package: package() };
+        let entry = "\
+* Thu May 02 2024 Ada <ada@example.com> - 1.2.0-1
+- UI: menu crash
+- UI: a description long enough to go past the eighty characters of a changelog
+  line
+";
+        assert_eq!(rpm.render(&release("v1.2.0")).unwrap(), entry);
+
+        let spec = "Name: towers\n\n%changelog\n* Mon Mar 11 2024 Ada <ada@example.com> - 1.1.0-1\n- first\n";
+        let result = rpm.insert(spec, &release("v1.2.0")).unwrap();
+        assert_eq!(
+            result,
+            format!("Name: towers\n\n%changelog\n{entry}\n* Mon Mar 11 2024 Ada <ada@example.com> - 1.1.0-1\n- first\n")
+        );
+        assert!(rpm.insert(&result, &release("v1.2.0")).is_err());
+    }
+}
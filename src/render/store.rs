@@ -0,0 +1,188 @@
+use super::{Entry, Release, Renderer};
+
+/// The notes as the BBCode of a Steam announcement, one `[h2]` per kind and `[h3]`
+/// per scope.
+pub struct Steam;
+
+/// The notes as the Markdown of an itch.io devlog, one `##` heading per kind and
+/// `###` per scope.
+pub struct Itch;
+
+/// `text` kept from the BBCode parser when it holds square brackets.
+fn bbcode(text: &str) -> String {
+    if text.contains('[') {
+        format!("[noparse]{text}[/noparse]")
+    } else {
+        text.to_string()
+    }
+}
+
+/// The entries, followed by their breaking notes with `notes`.
+fn steam_list(entries: &[Entry], notes: bool) -> String {
+    let mut list = String::from("[list]\n");
+    for entry in entries {
+        list.push_str(&format!("[*]{}\n", bbcode(&entry.content)));
+        for line in entry.breaking_note.lines().filter(|_| notes) {
+            list.push_str(&format!("{}\n", bbcode(line)));
+        }
+    }
+    list.push_str("[/list]\n");
+    list
+}
+
+/// The entries as a Markdown list, followed by their breaking notes with `notes`.
+fn itch_list(entries: &[Entry], notes: bool) -> String {
+    let mut list = String::new();
+    for entry in entries {
+        list.push_str(&format!("- {}\n", entry.content));
+        for line in entry.breaking_note.lines().filter(|_| notes) {
+            list.push_str(&format!("  {line}\n"));
+        }
+    }
+    list
+}
+
+impl Renderer for Steam {
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let mut notes = String::new();
+        if !release.breaking.is_empty() {
+            notes.push_str(&format!("[h2]{}[/h2]\n", bbcode(&release.breaking_label)));
+            notes.push_str(&steam_list(&release.breaking, true));
+        }
+        for section in &release.sections {
+            notes.push_str(&format!("[h2]{}[/h2]\n", bbcode(&section.label)));
+            for scope in &section.scopes {
+                if let Some(label) = &scope.label {
+                    notes.push_str(&format!("[h3]{}[/h3]\n", bbcode(label)));
+                }
+                notes.push_str(&steam_list(&scope.entries, false));
+            }
+        }
+        Ok(notes)
+    }
+
+    fn render_section(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(format!(
+            "[h1]{}[/h1]\n{}",
+            bbcode(&release.heading()),
+            self.render(release)?
+        ))
+    }
+}
+
+impl Renderer for Itch {
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let mut blocks = vec![];
+        if !release.breaking.is_empty() {
+            blocks.push(format!("## {}\n", release.breaking_label));
+            blocks.push(itch_list(&release.breaking, true));
+        }
+        for section in &release.sections {
+            blocks.push(format!("## {}\n", section.label));
+            for scope in &section.scopes {
+                if let Some(label) = &scope.label {
+                    blocks.push(format!("### {label}\n"));
+                }
+                blocks.push(itch_list(&scope.entries, false));
+            }
+        }
+        // The editor of itch.io only sees a list after a blank line
+        Ok(blocks.join("\n"))
+    }
+
+    fn render_section(&self, release: &Release) -> anyhow::Result<String> {
+        Ok(format!(
+            "# {}\n\n{}",
+            release.heading(),
+            self.render(release)?
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Scope, Section};
+
+    fn release() -> Release {
+        let entry = |content: &str| Entry {
+            content: content.to_string(),
+            ..Default::default()
+        };
+        Release {
+            tag: String::from("v0.2.0"),
+            date: Some(String::from("2024-05-01")),
+            breaking_label: String::from("Breaking changes"),
+            breaking: vec![Entry {
+                breaking_note: String::from("Old saves are lost"),
+                ..entry("new save format")
+            }],
+            sections: vec![Section {
+                kind: String::from("feat"),
+                label: String::from("New features"),
+                scopes: vec![
+                    Scope {
+                        name: String::from("tower"),
+                        label: Some(String::from("Towers")),
+                        entries: vec![entry("two more [fire] towers")],
+                    },
+                    Scope {
+                        name: String::from("other"),
+                        label: None,
+                        entries: vec![entry("new save format")],
+                    },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn steam_works() {
+        let notes = "\
+[h1]v0.2.0 (2024-05-01)[/h1]
+[h2]Breaking changes[/h2]
+[list]
+[*]new save format
+Old saves are lost
+[/list]
+[h2]New features[/h2]
+[h3]Towers[/h3]
+[list]
+[*][noparse]two more [fire] towers[/noparse]
+[/list]
+[list]
+[*]new save format
+[/list]
+";
+        assert_eq!(Steam.render_section(&release()).unwrap(), notes);
+    }
+
+    #[test]
+    fn itch_works() {
+        let notes = "\
+## Breaking changes
+
+- new save format
+  Old saves are lost
+
+## New features
+
+### Towers
+
+- two more [fire] towers
+
+- new save format
+";
+        assert_eq!(Itch.render(&release()).unwrap(), notes);
+    }
+}
@@ -0,0 +1,246 @@
+use super::{Entry, Reference, Release, Renderer};
+use minijinja::Environment;
+use serde::Serialize;
+use std::path::Path;
+
+/// The notes written by a Jinja template of the project, chosen with `--template`.
+pub struct Template {
+    /// Name of the template in the error messages, usually its path.
+    name: String,
+    source: String,
+    extension: String,
+}
+
+#[derive(Serialize)]
+struct ReleaseContext<'a> {
+    tag: &'a str,
+    version: &'a str,
+    previous_tag: Option<&'a str>,
+    date: Option<&'a str>,
+    language: &'a str,
+    compare_url: Option<&'a str>,
+    breaking_label: &'a str,
+    breaking: Vec<EntryContext<'a>>,
+    kinds: Vec<KindContext<'a>>,
+    /// Every author of the release, in order of appearance.
+    contributors: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct KindContext<'a> {
+    kind: &'a str,
+    label: &'a str,
+    scopes: Vec<ScopeContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct ScopeContext<'a> {
+    name: &'a str,
+    label: Option<&'a str>,
+    entries: Vec<EntryContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct EntryContext<'a> {
+    description: &'a str,
+    hash: &'a str,
+    authors: &'a [String],
+    breaking: bool,
+    breaking_note: Option<&'a str>,
+    refs: &'a [String],
+    references: &'a [Reference],
+    short_hash: &'a str,
+    url: Option<&'a str>,
+}
+
+impl<'a> EntryContext<'a> {
+    fn new(entry: &'a Entry) -> EntryContext<'a> {
+        EntryContext {
+            description: &entry.content,
+            hash: &entry.hash,
+            authors: &entry.authors,
+            breaking: entry.breaking,
+            breaking_note: Some(entry.breaking_note.as_str()).filter(|note| !note.is_empty()),
+            refs: &entry.refs,
+            references: &entry.references,
+            short_hash: entry.short_hash(),
+            url: entry.url.as_deref(),
+        }
+    }
+}
+
+impl<'a> ReleaseContext<'a> {
+    fn new(release: &'a Release) -> ReleaseContext<'a> {
+        let mut contributors: Vec<&str> = vec![];
+        let authors = release
+            .sections
+            .iter()
+            .flat_map(|section| &section.scopes)
+            .flat_map(|scope| &scope.entries)
+            .flat_map(|entry| &entry.authors);
+        for author in authors {
+            if !contributors.contains(&author.as_str()) {
+                contributors.push(author);
+            }
+        }
+        ReleaseContext {
+            tag: &release.tag,
+            version: release.version(),
+            previous_tag: release.previous_tag.as_deref(),
+            date: release.date.as_deref(),
+            language: &release.language,
+            compare_url: release.compare_url.as_deref(),
+            breaking_label: &release.breaking_label,
+            breaking: release.breaking.iter().map(EntryContext::new).collect(),
+            kinds: release
+                .sections
+                .iter()
+                .map(|section| KindContext {
+                    kind: &section.kind,
+                    label: &section.label,
+                    scopes: section
+                        .scopes
+                        .iter()
+                        .map(|scope| ScopeContext {
+                            name: &scope.name,
+                            label: scope.label.as_deref(),
+                            entries: scope.entries.iter().map(EntryContext::new).collect(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            contributors,
+        }
+    }
+}
+
+/// The message of a template error, with the line it comes from.
+fn describe(error: minijinja::Error) -> anyhow::Error {
+    let mut message = String::from("Invalid template");
+    if let Some(name) = error.name() {
+        message.push_str(&format!(" '{name}'"));
+    }
+    if let Some(line) = error.line() {
+        message.push_str(&format!(", line {line}"));
+    }
+    match error.detail() {
+        Some(detail) => anyhow::anyhow!("{message}: {detail}"),
+        None => anyhow::anyhow!("{message}: {}", error.kind()),
+    }
+}
+
+impl Template {
+    /// Checks the syntax of `source`. The extension of the notes comes from `name`
+    /// without its `.j2` or `.jinja`: `notes.md.j2` writes `whats_new.md`.
+    pub fn new(name: &str, source: &str) -> anyhow::Result<Template> {
+        Environment::new()
+            .template_from_named_str(name, source)
+            .map_err(describe)?;
+        let file = name
+            .strip_suffix(".j2")
+            .or_else(|| name.strip_suffix(".jinja"))
+            .unwrap_or(name);
+        let extension = Path::new(file)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_string())
+            .unwrap_or_else(|| String::from("txt"));
+        Ok(Template {
+            name: name.to_string(),
+            source: source.to_string(),
+            extension,
+        })
+    }
+
+    /// Reads the template in `file`.
+    pub fn read(file: &str) -> anyhow::Result<Template> {
+        let source = std::fs::read_to_string(file)
+            .map_err(|error| anyhow::anyhow!("Could not read '{file}': {error}"))?;
+        Template::new(file, &source)
+    }
+}
+
+impl Renderer for Template {
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn render(&self, release: &Release) -> anyhow::Result<String> {
+        let mut environment = Environment::new();
+        environment.set_trim_blocks(true);
+        environment.set_keep_trailing_newline(true);
+        environment
+            .render_named_str(&self.name, &self.source, ReleaseContext::new(release))
+            .map_err(describe)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render::{Scope, Section};
+
+    fn release() -> Release {
+        let entry = |content: &str, author: &str| Entry {
+            content: content.to_string(),
+            authors: vec![author.to_string()],
+            ..Default::default()
+        };
+        Release {
+            tag: String::from("v0.2.0"),
+            date: Some(String::from("2024-05-01")),
+            sections: vec![Section {
+                kind: String::from("feat"),
+                label: String::from("New features"),
+                scopes: vec![Scope {
+                    name: String::from("ui"),
+                    label: Some(String::from("UI")),
+                    entries: vec![
+                        entry("settings", "Ada"),
+                        entry("dark theme", "Grace"),
+                        entry("menu", "Ada"),
+                    ],
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn templates_work() {
+        let source = "\
+{{ version }} - {{ date }}
+{% for kind in kinds %}
+{{ kind.label | upper }}
+{% for scope in kind.scopes %}{% for entry in scope.entries %}
+* [{{ scope.label }}] {{ entry.description }}
+{% endfor %}{% endfor %}{% endfor %}
+Thanks to {{ contributors | join(\", \") }}
+";
+        let template = Template::new("notes.txt.j2", source).unwrap();
+        assert_eq!(template.extension(), "txt");
+        let notes = "\
+0.2.0 - 2024-05-01
+NEW FEATURES
+* [UI] settings
+* [UI] dark theme
+* [UI] menu
+Thanks to Ada, Grace
+";
+        assert_eq!(template.render(&release()).unwrap(), notes);
+
+        let error = Template::new("notes.md", "{{ tag }}\n{% for kind in kinds %}\n")
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid template 'notes.md', line 2"));
+        let error = Template::new("notes.md", "{{ tag }}\n\n{{ tag | nope }}")
+            .unwrap()
+            .render(&release())
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Invalid template 'notes.md', line 3"));
+    }
+}
@@ -0,0 +1,84 @@
+//! Dates of the package changelogs and feeds, in UTC.
+
+use super::Release;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Year, month, day, week day (0 for Sunday), hours, minutes and seconds of a Unix
+/// time, in UTC.
+fn civil_time(time: i64) -> (i64, usize, i64, usize, i64, i64, i64) {
+    let days = time.div_euclid(86400);
+    let seconds = time.rem_euclid(86400);
+    // Howard Hinnant's civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let week_day = (days + 4).rem_euclid(7) as usize;
+    (
+        year,
+        month as usize,
+        day,
+        week_day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+const WEEK_DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The time of the release, or now for unreleased commits.
+pub fn release_time(release: &Release) -> i64 {
+    release.time.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() as i64)
+    })
+}
+
+/// RFC 2822 date, as in `Thu, 02 May 2024 12:34:56 +0000`.
+pub fn rfc2822_date(time: i64) -> String {
+    let (year, month, day, week_day, hours, minutes, seconds) = civil_time(time);
+    format!(
+        "{}, {day:02} {} {year} {hours:02}:{minutes:02}:{seconds:02} +0000",
+        WEEK_DAYS[week_day],
+        MONTHS[month - 1]
+    )
+}
+
+/// Date of an RPM changelog entry, as in `Thu May 02 2024`.
+pub fn rpm_date(time: i64) -> String {
+    let (year, month, day, week_day, ..) = civil_time(time);
+    format!(
+        "{} {} {day:02} {year}",
+        WEEK_DAYS[week_day],
+        MONTHS[month - 1]
+    )
+}
+
+/// RFC 3339 date, as in `2024-05-02T12:34:56Z`.
+pub fn rfc3339_date(time: i64) -> String {
+    let (year, month, day, _, hours, minutes, seconds) = civil_time(time);
+    format!("{year}-{month:02}-{day:02}T{hours:02}:{minutes:02}:{seconds:02}Z")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates_work() {
+        assert_eq!(rfc2822_date(0), "Thu, 01 Jan 1970 00:00:00 +0000");
+        assert_eq!(rfc2822_date(1709164800), "Thu, 29 Feb 2024 00:00:00 +0000");
+        assert_eq!(rpm_date(1714653296), "Thu May 02 2024");
+        assert_eq!(rfc3339_date(1714653296), "2024-05-02T12:34:56Z");
+    }
+}